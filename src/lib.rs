@@ -71,12 +71,18 @@ mod macros;
 
 use parser::whitelist;
 use parser::field_whitelist;
+use parser::call_whitelist;
+use parser::skip::Skip;
+use parser::mutation_policies::{MutationPolicies, PolicyRef};
 
 use macros::private_fields;
 use macros::size_align;
 use macros::consumes;
 use macros::mutates;
 use macros::calls;
+use macros::items;
+use macros::field_policy;
+use macros::policies;
 
 // Function-like macros in Rust take only one TokenStream parameter and return a TokenStream.
 // https://doc.rust-lang.org/book/ch19-06-macros.html#how-to-write-a-custom-derive-macro
@@ -84,16 +90,28 @@ use proc_macro::TokenStream;
 
 use syn::{
     parse_macro_input, DeriveInput, 
-    ItemStruct, ItemFn};
+    ItemStruct, ItemFn, Item, ItemMod};
+
+/// Checks if a behavioral macro is written as `#[macro_name(skip)]`, which
+/// opts a function out of the policy applied to its impl block, trait or module.
+fn is_skipped(attr: &TokenStream) -> bool {
+    syn::parse::<Skip>(attr.clone()).is_ok()
+}
+
+/// Expands `#[macro_name(MyStruct::policy)]` through the policies declared by `mutation_policies`,
+/// or returns `None` if the attribute lists the fields itself.
+fn expand_policy(attr: &TokenStream, macro_name: &str, item: &TokenStream) -> Option<TokenStream> {
+    let policy_ref = syn::parse::<PolicyRef>(attr.clone()).ok()?;
+    Some(policies::expand_policy_ref(&policy_ref, macro_name, item.clone().into()).into())
+}
 
-    
 /// A procedural macro to assert that all fields in a struct are private.
 #[proc_macro_attribute]
 pub fn private_fields(attr: TokenStream, item: TokenStream) -> TokenStream {
     let input = parse_macro_input!(item as ItemStruct);
     let fields = parse_macro_input!(attr as whitelist::WhitelistArgs);
 
-    private_fields::assert_private_fields_impl(&fields.values, input).into()
+    private_fields::assert_private_fields_impl(&fields.values, input)
 }
 
 /// A procedural macro attribute to assert the size and alignment of a struct.
@@ -104,7 +122,7 @@ pub fn assert_align_size(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     let input = parse_macro_input!(item as DeriveInput);
 
-    size_align::assert_align_size_impl(size, align, &input).into()
+    size_align::assert_align_size_impl(size, align, &input)
 }
 
 /// A function consumes a list of instances of certain types. Allows to 
@@ -114,45 +132,232 @@ pub fn consumes(attr: TokenStream, item: TokenStream) -> TokenStream {
     let types = parse_macro_input!(attr as whitelist::WhitelistArgs);
     let input = parse_macro_input!(item as ItemFn);
 
-    consumes::assert_function_consumes_impl(&types.values, input).into()
+    consumes::assert_function_consumes_impl(&types.values, input)
 }
 
 
 /// Checks if a function includes all the whitelisted method calls.
-/// This macro ensures that only the methods listed in the whitelist are called within the function.
-/// If any method call outside of the whitelist is found, a compile-time error will be generated.
+/// If any of the whitelisted functions is never called, a compile-time error will be generated.
+///
+/// With the leading `only` keyword the macro also ensures that only the methods listed in
+/// the whitelist are called within the function. Every call or method call outside of the
+/// whitelist is then reported as a compile-time error at its call site.
+///
+/// Entries may be qualified, e.g. `"crate::mm::alloc"`, `"Vec::push"` or `"<Frame as Drop>::drop"`.
+/// A qualified entry matches a function call whose path ends with the entry, while an unqualified
+/// entry matches any function or method with that name. Method entries such as `"Self::flush"` or
+/// `"MyStruct::flush"` are checked against the receiver type whenever it is known from `self`, typed
+/// parameters or typed `let` bindings, while `"self.frames.push"` matches the receiver itself.
+/// Calls inside macro invocations such as `assert!` or `write!` are counted too, and macros
+/// themselves are listed as `"panic!"` or `"log::info!"`.
 /// 
-/// Usage: #[calls("func1", "func2", "func3"...)]
+/// The number of call sites can be constrained with `==`, `>=`, `<=`, `>` or `<`. Entries without
+/// a constraint are required to be called at least once.
+/// 
+/// With the `on_all_paths` keyword the calls are counted separately on every path through the function,
+/// following `if/else`, `match`, loops, early `return`, `?`, `break` and `continue`. A path reaching the end
+/// of the function without the expected calls generates a compile-time error. Calls inside closures and
+/// `async` blocks are not counted on any path, since they do not run in place.
+/// 
+/// The macro may also be applied to an impl block, a trait or an inline module, in which case every
+/// function inside is checked, including trait methods with a default body. A function with its own
+/// `#[calls(...)]` attribute overrides the enclosing policy, and `#[calls(skip)]` opts it out.
+///
+/// Usage: #[calls("func1", "func2", "func3"...)] or #[calls(only, "func1", "func2"...)]
+/// or #[calls("lock" == 1, "flush" >= 1, "alloc" <= 2)] or #[calls(on_all_paths, "release")]
 #[proc_macro_attribute]
 pub fn calls(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let whitelist = parse_macro_input!(attr as whitelist::WhitelistArgs);
-    let input = parse_macro_input!(item as ItemFn);
-    
-    calls::assert_call_impl(&whitelist.values, &input).into()
+    if is_skipped(&attr) {
+        return item;
+    }
+    let whitelist = parse_macro_input!(attr as call_whitelist::WhitelistArgs);
+    let input = parse_macro_input!(item as Item);
+
+    let mode = if whitelist.only { calls::Mode::Only } else { calls::Mode::Required };
+    items::assert_item_impl(input, "calls", |function, self_ty| {
+        calls::assert_call_impl(&whitelist.values, function, self_ty, mode, whitelist.on_all_paths)
+    }).into()
+}
+
+/// Checks that a function does not call any of the denylisted functions or methods.
+/// This macro is the reverse of `calls`: every call site of a listed name found
+/// within the function body is reported as a compile-time error. Macros are denylisted as `"panic!"`.
+///
+/// Like `calls`, it applies to impl blocks, traits and inline modules, and `#[nocalls(skip)]` opts a function out.
+///
+/// Usage: #[nocalls("func1", "func2", "func3"...)]
+#[proc_macro_attribute]
+pub fn nocalls(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if is_skipped(&attr) {
+        return item;
+    }
+    let denylist = parse_macro_input!(attr as whitelist::WhitelistArgs);
+    let input = parse_macro_input!(item as Item);
+
+    let denylist: Vec<_> = denylist.values.into_iter().map(call_whitelist::WhitelistEntry::from).collect();
+    items::assert_item_impl(input, "nocalls", |function, self_ty| {
+        calls::assert_call_impl(&denylist, function, self_ty, calls::Mode::Restricted, false)
+    }).into()
+}
+
+/// Checks that the listed functions are called in the listed order on every path through the function.
+/// A call may repeat the current step, but a listed call out of order, or a path reaching the end of
+/// the function before calling every listed function, generates a compile-time error.
+///
+/// Branches and `match` arms are followed separately, `while` and `for` bodies may run zero or more times,
+/// and early `return` or `?` leave the function. Calls inside closures are not part of the order.
+///
+/// Like `calls`, it applies to impl blocks, traits and inline modules, and `#[calls_in_order(skip)]` opts a function out.
+///
+/// Usage: #[calls_in_order("lock", "update_entry", "flush_tlb", "unlock")]
+#[proc_macro_attribute]
+pub fn calls_in_order(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if is_skipped(&attr) {
+        return item;
+    }
+    let order = parse_macro_input!(attr as whitelist::WhitelistArgs);
+    let input = parse_macro_input!(item as Item);
+
+    items::assert_item_impl(input, "calls_in_order", |function, self_ty| {
+        calls::assert_call_order_impl(&order.values, function, self_ty)
+    }).into()
 }
 
 /// Checks if only whitelisted fields of an instance type are mutated by a function.
 /// This macro enforces that only the fields listed in the whitelist can be mutated by the function.
 /// If any field not in the whitelist is mutated, a compile-time error will be generated.
 ///
-/// Usage: `#[mutates(MyStructName: "field1", "field2", "field3", ...)]`
+/// A field is mutated by assignments and compound assignments such as `+=`, mutable borrows such as
+/// `&mut self.field`, in-place methods such as `self.items.push(x)`, and `mem::swap`, `mem::replace`,
+/// `mem::take` or `ptr::write`. Overwriting the whole instance, e.g. `*self = MyStruct::new()`, is only
+/// allowed by the `"*"` wildcard, which whitelists every field. The whole body is searched, including
+/// closures, `async` blocks, macro arguments and the `else` branch of `let ... else`.
+///
+/// Instances are found among the arguments whose type names the struct, regardless of its module path
+/// and generic arguments, and looking through references, `Box`, `Rc`, `Arc`, `Pin`, `Option` and
+/// locks. `Self` is resolved when the macro is applied to an impl block, and is otherwise assumed to be the
+/// struct. Local bindings are instances if their written type names the struct, or if they are built by a
+/// struct literal or an associated function such as `MyStruct::with_capacity(n)` or `Self::new()`.
+///
+/// Bindings follow the lexical scopes of the function. References borrowed from an instance, such as
+/// `let r = &mut self.inner;` or `if let Some(child) = self.child.as_mut()`, are followed, so writes
+/// through them are checked against the original field, while shadowed bindings are no longer tracked.
+/// Patterns are followed as well: destructuring `let`s, `match` arms and `if let` on a borrowed field,
+/// `ref mut` bindings into a field, and `for` loops over `self.items.iter_mut()` or `&mut self.items`,
+/// whose elements are the field `items[]`.
+///
+/// Interior mutability counts too: `set`, `borrow_mut`, `lock`, `write`, `store`, `fetch_add` and the
+/// other well-known methods of `Cell`, `RefCell`, `Mutex`, `RwLock`, `UnsafeCell` and the atomics mutate the
+/// field they are called on, and so does `replace` with one argument, unlike `str::replace`. More methods
+/// are listed with `interior_methods = ("bump", ...)`. Fields behind a lock are listed by their nested
+/// path, e.g. `"state.count"` for `self.state.lock().unwrap().count = 0`. Since `&self` and by-value
+/// receivers can mutate this way, `self` is always an instance.
+///
+/// Taking a mutable raw pointer to a field, with `addr_of_mut!(self.field)`, `&raw mut self.field` or a cast
+/// to `*mut`, is a mutation of the field, and writes through such pointers inside `unsafe` blocks are followed.
+///
+/// Nested, tuple and indexed fields are listed as `"inner.count"`, `"0"` or `"table[]"`. A listed path
+/// also allows writes nested inside it, so `"inner"` covers `self.inner.count = 0`. The listed fields
+/// must exist: the function refers to them at compile time, so a misspelled or renamed field fails
+/// with rustc's own "no field" error. The fields are looked up through the impl type or an argument type,
+/// and on instances built in the body with a struct literal or declared with the struct type.
+///
+/// The macro may also be applied to an impl block, a trait or an inline module to check every function
+/// inside. A function with its own `#[mutates(...)]` attribute overrides the enclosing policy, and
+/// `#[mutates(skip)]` opts it out.
+///
+/// Several structs may be listed in one attribute, each with its own instances and whitelist. Without
+/// the impl type, `self` then belongs to the listed structs that are not passed as another argument. If
+/// several of them remain, the attribute must be applied to the impl block instead.
+///
+/// Usage: `#[mutates(MyStructName: ("field1", "field2", ...))]`
+/// or `#[mutates(Frame: ("state"), Allocator: ("free_list", "count"))]`
+/// or `#[mutates(MyStructName: ("counter"), interior_methods = ("bump"))]`
+/// or `#[mutates(MyStructName::policy)]` with a policy declared by `mutation_policies`
 #[proc_macro_attribute]
 pub fn mutates(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if is_skipped(&attr) {
+        return item;
+    }
+    if let Some(expanded) = expand_policy(&attr, "mutates", &item) {
+        return expanded;
+    }
     let macro_data = parse_macro_input!(attr as field_whitelist::WhitelistArgs);
-    let input = parse_macro_input!(item as ItemFn);
+    let input = parse_macro_input!(item as Item);
     
-    mutates::assert_mutate_impl(&macro_data, &input, false).into()
+    items::assert_item_impl(input, "mutates", |function, self_ty| {
+        let mut errors = mutates::assert_mutate_impl(&macro_data, function, self_ty, false);
+        errors.extend(mutates::reference_fields(&macro_data, function, self_ty));
+        errors
+    }).into()
 }
 
 #[proc_macro_attribute]
-/// Checks if a public field of an instance type is only mutated by specific whitelisted functions.
-/// This macro ensures that only the functions listed in the whitelist can mutate public fields of the instance type.
-/// If any public field is mutated by a function not in the whitelist, a compile-time error will be generated.
+/// Checks if the listed fields of an instance type are left unmutated by a function.
+/// This macro is the opposite of `mutates`: the fields listed in the denylist cannot be mutated by the function.
+/// If any listed field is mutated, a compile-time error will be generated. To restrict a field to a set of
+/// functions instead, see `enforce_mutations`.
+///
+/// Field paths are listed as in `mutates`. A write to `inner.count` also touches `inner`, so
+/// it is rejected if either of them is listed.
 ///
-/// Usage: `#[nomutates(MyStructName: "func1", "func2", "func3", ...)]`
+/// Like `mutates`, it applies to impl blocks, traits and inline modules, and `#[nomutates(skip)]` opts a function out.
+///
+/// Usage: `#[nomutates(MyStructName: ("field1", "field2", ...), OtherStruct: (...))]`
+/// or `#[nomutates(MyStructName::policy)]` with a policy declared by `mutation_policies`
 pub fn nomutates(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if is_skipped(&attr) {
+        return item;
+    }
+    if let Some(expanded) = expand_policy(&attr, "nomutates", &item) {
+        return expanded;
+    }
     let macro_data = parse_macro_input!(attr as field_whitelist::WhitelistArgs);
-    let input = parse_macro_input!(item as ItemFn);
+    let input = parse_macro_input!(item as Item);
     
-    mutates::assert_mutate_impl(&macro_data, &input, true).into()
+    items::assert_item_impl(input, "nomutates", |function, self_ty| {
+        let mut errors = mutates::assert_mutate_impl(&macro_data, function, self_ty, true);
+        errors.extend(mutates::reference_fields(&macro_data, function, self_ty));
+        errors
+    }).into()
+}
+
+/// Declares named lists of fields on a struct, so that `mutates` and `nomutates` can refer to
+/// them instead of repeating the fields on every function, e.g. `#[mutates(MyStruct::alloc)]`.
+/// Changing a list then takes one edit.
+///
+/// The policies are reachable within the crate wherever the struct is, e.g. through its path with
+/// `#[mutates(crate::frames::Frame::alloc)]`, or through `use crate::frames::Frame;` with
+/// `#[mutates(Frame::alloc)]`. An unknown policy generates a compile-time error.
+///
+/// Usage: `#[mutation_policies(alloc = ("free_list", "count"), reset = ("*"))]`
+#[proc_macro_attribute]
+pub fn mutation_policies(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let policies = parse_macro_input!(attr as MutationPolicies);
+    let input = parse_macro_input!(item as ItemStruct);
+
+    policies::assert_policies_impl(&policies, input).into()
+}
+
+/// Checks if the fields of the structs declared in a module are only mutated by specific functions.
+/// A field lists the functions allowed to mutate it with `#[mutated_only_by("set_state", "reset")]`,
+/// and any other function of the module mutating it generates a compile-time error naming the function.
+///
+/// Mutations are found as in `nomutates`, so nested writes, overwrites of the whole instance and mutable
+/// raw pointers count too. Functions are matched by name, and the scope covers the free functions, impl
+/// blocks and traits of the module and of its nested inline modules. A nested module with its own
+/// `#[enforce_mutations]` attribute is a separate scope.
+///
+/// Usage: `#[enforce_mutations] mod frames { pub struct Frame { #[mutated_only_by("map")] pub state: State } }`
+#[proc_macro_attribute]
+pub fn enforce_mutations(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        let attr = proc_macro2::TokenStream::from(attr);
+        return syn::Error::new_spanned(attr, "`enforce_mutations` takes no arguments")
+            .to_compile_error()
+            .into();
+    }
+    let input = parse_macro_input!(item as ItemMod);
+
+    field_policy::assert_field_policy_impl(input).into()
 }
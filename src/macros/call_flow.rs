@@ -0,0 +1,328 @@
+use syn::{
+    Block, Expr, ExprBlock, ExprBreak, ExprContinue, ExprForLoop,
+    ExprIf, ExprLoop, ExprMatch, ExprWhile, Label, Local, Macro, Stmt};
+use std::collections::BTreeSet;
+use crate::parser::macro_args::parse_macro_args;
+use super::calls::CallSite;
+use super::call_types::TypeScopes;
+
+/// Macros that never return, so the path ends at their invocation.
+const DIVERGING_MACROS: &[&str] = &["panic", "unreachable", "todo", "unimplemented"];
+
+/// The abstract states of every path reaching a program point. An empty set
+/// means that no path reaches it, e.g. right after a `return` or a `panic!`.
+pub type States<S> = BTreeSet<S>;
+
+/// A lightweight control-flow walker over a function body.
+///
+/// Every path through the function carries an abstract state, which is updated by
+/// `transfer` at each call site in evaluation order. The walker follows `if/else`,
+/// `match`, loops, early `return`, `?`, `break` and `continue`:
+/// - `while` and `for` bodies may run zero or more times, `loop` bodies at least once;
+/// - loops are re-walked until the set of states reaching them stops growing;
+/// - closures and `async` blocks are not executed where they are defined, so their calls are skipped.
+pub struct FlowWalker<S, F> {
+    transfer: F,
+    scopes: TypeScopes,
+    /// States of the paths leaving the function.
+    exits: States<S>,
+    /// The enclosing loops and labeled blocks, innermost last.
+    loops: Vec<LoopScope<S>>,
+}
+
+struct LoopScope<S> {
+    label: Option<String>,
+    /// Labeled blocks can only be left with `break 'label`.
+    is_block: bool,
+    breaks: States<S>,
+    continues: States<S>,
+}
+
+impl<S: Ord + Clone, F: FnMut(&S, &CallSite) -> S> FlowWalker<S, F> {
+    pub fn new(transfer: F, scopes: TypeScopes) -> Self {
+        FlowWalker { transfer, scopes, exits: BTreeSet::new(), loops: Vec::new() }
+    }
+
+    /// Walks the function body and returns the states of every path reaching the function exit.
+    pub fn walk_function(mut self, block: &Block, initial: S) -> States<S> {
+        let fallthrough = self.block(block, BTreeSet::from([initial]));
+        self.exits.extend(fallthrough);
+        self.exits
+    }
+
+    fn call(&mut self, site: CallSite, states: States<S>) -> States<S> {
+        states.iter().map(|state| (self.transfer)(state, &site)).collect()
+    }
+
+    fn block(&mut self, block: &Block, mut states: States<S>) -> States<S> {
+        self.scopes.push();
+        for stmt in &block.stmts {
+            states = match stmt {
+                Stmt::Expr(expr, _) => self.expr(expr, states),
+                Stmt::Local(local) => self.local(local, states),
+                Stmt::Macro(stmt) => self.mac(&stmt.mac, states),
+                // Nested items are not part of the function body.
+                Stmt::Item(_) => states,
+            };
+        }
+        self.scopes.pop();
+        states
+    }
+
+    fn local(&mut self, local: &Local, mut states: States<S>) -> States<S> {
+        if let Some(init) = &local.init {
+            states = self.expr(&init.expr, states);
+            if let Some((_, diverge)) = &init.diverge {
+                // The `else` branch of `let ... else` never falls through.
+                self.expr(diverge, states.clone());
+            }
+        }
+        self.scopes.bind(&local.pat);
+        states
+    }
+
+    fn exprs<'a>(&mut self, exprs: impl IntoIterator<Item = &'a Expr>, mut states: States<S>) -> States<S> {
+        for expr in exprs {
+            states = self.expr(expr, states);
+        }
+        states
+    }
+
+    fn mac(&mut self, mac: &Macro, states: States<S>) -> States<S> {
+        let states = self.exprs(&parse_macro_args(mac), states);
+        let states = self.call(CallSite::from_macro(mac), states);
+
+        let diverges = mac.path.segments.last()
+            .is_some_and(|seg| DIVERGING_MACROS.contains(&seg.ident.to_string().as_str()));
+        if diverges { BTreeSet::new() } else { states }
+    }
+
+    fn expr(&mut self, expr: &Expr, states: States<S>) -> States<S> {
+        // Nothing to explore once every path has left.
+        if states.is_empty() {
+            return states;
+        }
+
+        match expr {
+            Expr::Call(call) => {
+                let states = self.expr(&call.func, states);
+                let states = self.exprs(&call.args, states);
+                match CallSite::from_call(call) {
+                    Some(site) => self.call(site, states),
+                    None => states,
+                }
+            }
+
+            Expr::MethodCall(call) => {
+                let states = self.expr(&call.receiver, states);
+                let states = self.exprs(&call.args, states);
+                self.call(CallSite::from_method_call(call, &self.scopes), states)
+            }
+
+            Expr::Macro(expr) => self.mac(&expr.mac, states),
+
+            Expr::Block(ExprBlock { block, label, .. }) => match label {
+                // A labeled block can be left early with `break 'label`.
+                Some(label) => {
+                    self.loops.push(LoopScope::new(Some(label), true));
+                    let states = self.block(block, states);
+                    let scope = self.loops.pop().unwrap();
+                    states.into_iter().chain(scope.breaks).collect()
+                }
+                None => self.block(block, states),
+            },
+            Expr::Unsafe(expr) => self.block(&expr.block, states),
+            Expr::TryBlock(expr) => self.block(&expr.block, states),
+
+            Expr::If(ExprIf { cond, then_branch, else_branch, .. }) => {
+                let states = self.expr(cond, states);
+                let mut after = self.block(then_branch, states.clone());
+                match else_branch {
+                    Some((_, else_expr)) => after.extend(self.expr(else_expr, states)),
+                    None => after.extend(states),
+                }
+                after
+            }
+
+            Expr::Match(ExprMatch { expr, arms, .. }) => {
+                let states = self.expr(expr, states);
+                let mut after = BTreeSet::new();
+                for arm in arms {
+                    self.scopes.push();
+                    self.scopes.bind(&arm.pat);
+                    let mut arm_states = states.clone();
+                    if let Some((_, guard)) = &arm.guard {
+                        arm_states = self.expr(guard, arm_states);
+                    }
+                    after.extend(self.expr(&arm.body, arm_states));
+                    self.scopes.pop();
+                }
+                after
+            }
+
+            Expr::While(ExprWhile { label, cond, body, .. }) => {
+                // The condition runs before each iteration and once more before leaving.
+                self.loops.push(LoopScope::new(label.as_ref(), false));
+                let mut head = states.clone();
+                loop {
+                    let cond_states = self.expr(cond, head.clone());
+                    let next = self.iteration(body, cond_states, &states);
+                    if next == head {
+                        break;
+                    }
+                    head = next;
+                }
+                let exit = self.expr(cond, head);
+                let scope = self.loops.pop().unwrap();
+                exit.into_iter().chain(scope.breaks).collect()
+            }
+
+            Expr::ForLoop(ExprForLoop { label, pat, expr, body, .. }) => {
+                let states = self.expr(expr, states);
+                self.scopes.push();
+                self.scopes.bind(pat);
+                self.loops.push(LoopScope::new(label.as_ref(), false));
+                let head = self.fixpoint(body, states);
+                let scope = self.loops.pop().unwrap();
+                self.scopes.pop();
+                head.into_iter().chain(scope.breaks).collect()
+            }
+
+            Expr::Loop(ExprLoop { label, body, .. }) => {
+                // The loop is only left through `break`, `return` or `?`.
+                self.loops.push(LoopScope::new(label.as_ref(), false));
+                self.fixpoint(body, states);
+                self.loops.pop().unwrap().breaks
+            }
+
+            Expr::Break(ExprBreak { label, expr, .. }) => {
+                let states = match expr {
+                    Some(expr) => self.expr(expr, states),
+                    None => states,
+                };
+                if let Some(scope) = self.scope(label.as_ref().map(|l| l.ident.to_string()), true) {
+                    scope.breaks.extend(states);
+                }
+                BTreeSet::new()
+            }
+
+            Expr::Continue(ExprContinue { label, .. }) => {
+                if let Some(scope) = self.scope(label.as_ref().map(|l| l.ident.to_string()), false) {
+                    scope.continues.extend(states);
+                }
+                BTreeSet::new()
+            }
+
+            Expr::Return(expr) => {
+                let states = match &expr.expr {
+                    Some(expr) => self.expr(expr, states),
+                    None => states,
+                };
+                self.exits.extend(states);
+                BTreeSet::new()
+            }
+
+            Expr::Try(expr) => {
+                // The `?` operator may leave the function early.
+                let states = self.expr(&expr.expr, states);
+                self.exits.extend(states.clone());
+                states
+            }
+
+            Expr::Binary(expr) => {
+                let states = self.expr(&expr.left, states);
+                match expr.op {
+                    // The right operand of `&&` and `||` may be skipped.
+                    syn::BinOp::And(_) | syn::BinOp::Or(_) => {
+                        let mut after = self.expr(&expr.right, states.clone());
+                        after.extend(states);
+                        after
+                    }
+                    _ => self.expr(&expr.right, states),
+                }
+            }
+
+            Expr::Assign(expr) => {
+                let states = self.expr(&expr.right, states);
+                self.expr(&expr.left, states)
+            }
+            Expr::Array(expr) => self.exprs(&expr.elems, states),
+            Expr::Tuple(expr) => self.exprs(&expr.elems, states),
+            Expr::Await(expr) => self.expr(&expr.base, states),
+            Expr::Cast(expr) => self.expr(&expr.expr, states),
+            Expr::Field(expr) => self.expr(&expr.base, states),
+            Expr::Group(expr) => self.expr(&expr.expr, states),
+            Expr::Paren(expr) => self.expr(&expr.expr, states),
+            Expr::Reference(expr) => self.expr(&expr.expr, states),
+            Expr::RawAddr(expr) => self.expr(&expr.expr, states),
+            Expr::Unary(expr) => self.expr(&expr.expr, states),
+            Expr::Let(expr) => {
+                let states = self.expr(&expr.expr, states);
+                self.scopes.bind(&expr.pat);
+                states
+            }
+            Expr::Index(expr) => {
+                let states = self.expr(&expr.expr, states);
+                self.expr(&expr.index, states)
+            }
+            Expr::Range(expr) => {
+                let states = self.exprs(expr.start.as_deref(), states);
+                self.exprs(expr.end.as_deref(), states)
+            }
+            Expr::Repeat(expr) => {
+                let states = self.expr(&expr.expr, states);
+                self.expr(&expr.len, states)
+            }
+            Expr::Struct(expr) => {
+                let states = self.exprs(expr.fields.iter().map(|field| &field.expr), states);
+                self.exprs(expr.rest.as_deref(), states)
+            }
+            Expr::Yield(expr) => self.exprs(expr.expr.as_deref(), states),
+
+            // Closures, `async` and `const` blocks are not executed in place,
+            // literals and paths do not call anything.
+            _ => states,
+        }
+    }
+
+    /// Walks a loop body that may run any number of times, until the set of states
+    /// at the loop head stops growing. Returns the states at the loop head.
+    fn fixpoint(&mut self, body: &Block, states: States<S>) -> States<S> {
+        let mut head = states.clone();
+        loop {
+            let next = self.iteration(body, head.clone(), &states);
+            if next == head {
+                return head;
+            }
+            head = next;
+        }
+    }
+
+    /// Walks one iteration of a loop body and returns the states at the next loop head.
+    fn iteration(&mut self, body: &Block, states: States<S>, entry: &States<S>) -> States<S> {
+        let mut next = self.block(body, states);
+        let scope = self.loops.last_mut().unwrap();
+        next.extend(std::mem::take(&mut scope.continues));
+        next.extend(entry.iter().cloned());
+        next
+    }
+
+    /// Finds the loop targeted by `break` or `continue`, plain ones skip labeled blocks.
+    fn scope(&mut self, label: Option<String>, is_break: bool) -> Option<&mut LoopScope<S>> {
+        self.loops.iter_mut().rev().find(|scope| match &label {
+            Some(label) => scope.label.as_ref() == Some(label) && (is_break || !scope.is_block),
+            None => !scope.is_block,
+        })
+    }
+}
+
+impl<S> LoopScope<S> {
+    fn new(label: Option<&Label>, is_block: bool) -> Self {
+        LoopScope {
+            label: label.map(|label| label.name.ident.to_string()),
+            is_block,
+            breaks: BTreeSet::new(),
+            continues: BTreeSet::new(),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use syn::{
+    visit::{self, Visit},
+    Expr, ExprPath, FnArg, GenericArgument, Member,
+    Pat, PatIdent, PatType, PathArguments, Signature, Type};
+use std::collections::HashMap;
+
+/// Smart pointers whose methods are rarely called directly, so method calls are
+/// matched against the pointee type instead, e.g. `Box<Frame>` resolves to `Frame`.
+const TRANSPARENT_TYPES: &[&str] = &["Box", "Rc", "Arc"];
+
+/// The syntactic type of a method call receiver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReceiverType {
+    /// The type cannot be resolved without type inference.
+    Unknown,
+    /// The `Self` type, with the name of the impl type when known.
+    SelfType(Option<String>),
+    /// A named type, e.g. `Vec` for `Vec<u8>`.
+    Named(String),
+}
+
+impl ReceiverType {
+    /// Checks if a method listed as `Qualifier::method` may be called on this receiver.
+    /// Unknown receivers admit every qualifier, as does `Self` if the impl type is unknown.
+    pub fn admits(&self, qualifier: &str) -> bool {
+        match self {
+            ReceiverType::Unknown | ReceiverType::SelfType(None) => true,
+            ReceiverType::SelfType(Some(name)) => qualifier == "Self" || qualifier == name,
+            ReceiverType::Named(name) => qualifier == name,
+        }
+    }
+}
+
+/// Typed bindings visible at a program point, the innermost scope last.
+///
+/// Bindings come from `self`, typed function parameters, typed `let` bindings and typed
+/// closure parameters. Every other binding shadows outer ones with an unknown type.
+pub struct TypeScopes {
+    self_ty: Option<String>,
+    scopes: Vec<HashMap<String, ReceiverType>>,
+}
+
+impl TypeScopes {
+    pub fn new(sig: &Signature, self_ty: Option<String>) -> Self {
+        let mut scopes = TypeScopes { self_ty, scopes: vec![HashMap::new()] };
+
+        for arg in &sig.inputs {
+            match arg {
+                FnArg::Receiver(_) => {
+                    let self_ty = ReceiverType::SelfType(scopes.self_ty.clone());
+                    scopes.insert("self".to_string(), self_ty);
+                }
+                FnArg::Typed(pat_type) => scopes.bind_typed(&pat_type.pat, &pat_type.ty),
+            }
+        }
+        scopes
+    }
+
+    pub fn push(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    pub fn pop(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Introduces the bindings of a pattern into the innermost scope.
+    pub fn bind(&mut self, pat: &Pat) {
+        match pat {
+            Pat::Type(PatType { pat, ty, .. }) => self.bind_typed(pat, ty),
+            _ => self.bind_unknown(pat),
+        }
+    }
+
+    fn bind_typed(&mut self, pat: &Pat, ty: &Type) {
+        match pat {
+            Pat::Ident(PatIdent { ident, subpat: None, .. }) => {
+                let ty = self.resolve(ty);
+                self.insert(ident.to_string(), ty);
+            }
+            _ => self.bind_unknown(pat),
+        }
+    }
+
+    fn bind_unknown(&mut self, pat: &Pat) {
+        let mut collector = IdentCollector(Vec::new());
+        collector.visit_pat(pat);
+        for ident in collector.0 {
+            self.insert(ident, ReceiverType::Unknown);
+        }
+    }
+
+    fn insert(&mut self, name: String, ty: ReceiverType) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, ty);
+        }
+    }
+
+    /// Resolves a written type, looking through references and transparent smart pointers.
+    fn resolve(&self, ty: &Type) -> ReceiverType {
+        match ty {
+            Type::Reference(reference) => self.resolve(&reference.elem),
+            Type::Paren(paren) => self.resolve(&paren.elem),
+            Type::Group(group) => self.resolve(&group.elem),
+            Type::Path(type_path) if type_path.qself.is_none() => {
+                let Some(last) = type_path.path.segments.last() else {
+                    return ReceiverType::Unknown;
+                };
+                let name = last.ident.to_string();
+
+                if TRANSPARENT_TYPES.contains(&name.as_str()) {
+                    if let PathArguments::AngleBracketed(args) = &last.arguments {
+                        if let Some(GenericArgument::Type(inner)) = args.args.first() {
+                            return self.resolve(inner);
+                        }
+                    }
+                }
+
+                if name == "Self" || self.self_ty.as_ref() == Some(&name) {
+                    return ReceiverType::SelfType(self.self_ty.clone());
+                }
+                ReceiverType::Named(name)
+            }
+            _ => ReceiverType::Unknown,
+        }
+    }
+
+    fn lookup(&self, name: &str) -> ReceiverType {
+        self.scopes.iter().rev()
+            .find_map(|scope| scope.get(name))
+            .cloned()
+            .unwrap_or(ReceiverType::Unknown)
+    }
+
+    /// Resolves the type of a method call receiver from its syntax.
+    pub fn receiver_type(&self, expr: &Expr) -> ReceiverType {
+        match expr {
+            Expr::Path(ExprPath { qself: None, path, .. }) => match path.get_ident() {
+                Some(ident) => self.lookup(&ident.to_string()),
+                None => ReceiverType::Unknown,
+            },
+            Expr::Struct(expr) if expr.qself.is_none() => match expr.path.segments.last() {
+                Some(seg) if seg.ident == "Self" => ReceiverType::SelfType(self.self_ty.clone()),
+                Some(seg) => ReceiverType::Named(seg.ident.to_string()),
+                None => ReceiverType::Unknown,
+            },
+            Expr::Paren(expr) => self.receiver_type(&expr.expr),
+            Expr::Group(expr) => self.receiver_type(&expr.expr),
+            Expr::Reference(expr) => self.receiver_type(&expr.expr),
+            Expr::Unary(expr) if matches!(expr.op, syn::UnOp::Deref(_)) => self.receiver_type(&expr.expr),
+            _ => ReceiverType::Unknown,
+        }
+    }
+}
+
+/// Renders a receiver such as `self.frames` or `self.0` as a dotted path.
+pub fn receiver_path(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Path(ExprPath { qself: None, path, .. }) => path.get_ident().map(|ident| ident.to_string()),
+        Expr::Field(field) => {
+            let member = match &field.member {
+                Member::Named(ident) => ident.to_string(),
+                Member::Unnamed(index) => index.index.to_string(),
+            };
+            receiver_path(&field.base).map(|base| format!("{}.{}", base, member))
+        }
+        Expr::Paren(expr) => receiver_path(&expr.expr),
+        Expr::Group(expr) => receiver_path(&expr.expr),
+        _ => None,
+    }
+}
+
+/// Collects the names bound by a pattern.
+pub struct IdentCollector(pub Vec<String>);
+
+impl<'ast> Visit<'ast> for IdentCollector {
+    fn visit_pat_ident(&mut self, pat: &'ast PatIdent) {
+        self.0.push(pat.ident.to_string());
+        visit::visit_pat_ident(self, pat);
+    }
+}
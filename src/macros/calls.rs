@@ -1,67 +1,390 @@
 use syn::{
-    Block, Local, ExprIf, ExprWhile, 
-    ExprForLoop, ExprMethodCall, ExprBlock, 
-    ItemFn, Expr, ExprCall, Stmt, ExprClosure};
-use proc_macro2::TokenStream as ProcTokenStream;
-use std::collections::HashSet;
-use proc_macro::TokenStream;
+    visit::{self, Visit},
+    Arm, Block, ExprClosure, ExprForLoop, ExprLet, ExprMethodCall, ExprPath,
+    Item, Local, Macro, ItemFn, Expr, ExprCall, QSelf, Type};
+use proc_macro2::{Span, TokenStream as ProcTokenStream};
+use syn::spanned::Spanned;
 use quote::quote;
+use crate::parser::macro_args::parse_macro_args;
+use crate::call_whitelist::{Count, WhitelistEntry};
+use super::call_flow::FlowWalker;
+use super::call_types::{receiver_path, ReceiverType, TypeScopes};
 
-pub fn assert_call_impl(whitelist: &[String], function: &ItemFn) -> ProcTokenStream {
-    let mut errors = Vec::new();
-    let block: &Box<Block> = &function.block;
-    let mut called_functions = HashSet::new();
+/// Describes how the listed functions are enforced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Every listed function must be called at least once.
+    Required,
+    /// Every listed function must be called, and nothing else may be called.
+    Only,
+    /// None of the listed functions may be called.
+    Restricted,
+}
+
+/// Checks the calls of a function against the listed entries, and returns the compile errors.
+/// The impl type of a method, if any, resolves the `Self` receivers.
+pub fn assert_call_impl(
+    whitelist: &[WhitelistEntry],
+    function: &ItemFn,
+    self_ty: Option<&Type>,
+    mode: Mode,
+    on_all_paths: bool,
+) -> ProcTokenStream {
+    let block: &Block = &function.block;
+    let entries: Vec<Entry> = whitelist.iter().map(Entry::parse).collect();
 
     // Start the recursive checking from the function body.
-    check_block_for_calls(block, whitelist, &mut errors, &mut called_functions);
+    let mut visitor = CallVisitor {
+        whitelist: &entries,
+        errors: Vec::new(),
+        call_counts: vec![0; entries.len()],
+        mode,
+        scopes: TypeScopes::new(&function.sig, self_ty.map(type_name)),
+    };
+    visitor.visit_block(block);
+    let CallVisitor { mut errors, call_counts, .. } = visitor;
 
-    let whitelist_set: HashSet<String> = whitelist.iter().cloned().collect();
-    let missed_calls: Vec<_> = whitelist_set.difference(&called_functions).collect();
+    // Required and strict modes expect every listed function to be called
+    // at least once, unless the entry specifies its own number of call sites.
+    if mode != Mode::Restricted && on_all_paths {
+        errors.extend(check_all_paths(&entries, function, self_ty));
+    } else if mode != Mode::Restricted {
+        for (entry, &found) in entries.iter().zip(&call_counts) {
+            if let Some(message) = entry.check_count(found, "call sites") {
+                errors.push(Error::new(message, function.sig.ident.span()));
+            }
+        }
+    }
+
+    to_compile_errors(&errors)
+}
 
-    if !missed_calls.is_empty() {
-        for missed in missed_calls {
-            errors.push(Error::new(format!("Function `{}` not called", missed)));
+/// Checks the expected number of calls on every path through the function.
+///
+/// Each path counts the calls of every entry, capped just above the expected number
+/// so that loops reach a fixpoint. The counts are checked on every path leaving the function.
+fn check_all_paths(entries: &[Entry], function: &ItemFn, self_ty: Option<&Type>) -> Vec<Error> {
+    let caps: Vec<usize> = entries.iter()
+        .map(|entry| entry.count.map_or(1, |count| count.value + 1))
+        .collect();
+
+    let transfer = |counts: &Vec<usize>, site: &CallSite| -> Vec<usize> {
+        let mut counts = counts.clone();
+        for (index, entry) in entries.iter().enumerate() {
+            if entry.matches(site) {
+                counts[index] = (counts[index] + 1).min(caps[index]);
+            }
+        }
+        counts
+    };
+
+    let scopes = TypeScopes::new(&function.sig, self_ty.map(type_name));
+    let exits = FlowWalker::new(transfer, scopes).walk_function(&function.block, vec![0; entries.len()]);
+
+    // Report every entry once, even if several paths violate it.
+    let mut errors: Vec<Error> = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let violation = exits.iter().find_map(|counts| match entry.count {
+            None if counts[index] == 0 => {
+                Some(format!("Function can return without calling `{}`", entry.name))
+            }
+            _ => entry.check_count(counts[index], "calls on some path"),
+        });
+        if let Some(message) = violation {
+            errors.push(Error::new(message, function.sig.ident.span()));
         }
     }
+    errors
+}
+
+/// Checks that the listed calls happen in the listed order on every path through the function.
+///
+/// Each path keeps the number of steps completed so far. A call to the current step may repeat,
+/// a call to the next step advances, and any other listed call is reported as out of order.
+/// Every path reaching the function exit must have completed all of the steps.
+pub fn assert_call_order_impl(order: &[String], function: &ItemFn, self_ty: Option<&Type>) -> ProcTokenStream {
+    let entries: Vec<Entry> = order.iter()
+        .map(|name| Entry::parse(&WhitelistEntry::from(name.clone())))
+        .collect();
+    let mut errors: Vec<Error> = Vec::new();
+    let mut reported: Vec<(usize, String)> = Vec::new();
+
+    let transfer = |&step: &usize, site: &CallSite| -> usize {
+        let matches = |index: usize| entries.get(index).is_some_and(|entry| entry.matches(site));
+
+        if matches(step) {
+            return step + 1;
+        }
+        if step > 0 && matches(step - 1) {
+            return step;
+        }
+
+        // Any other listed call breaks the order.
+        let Some(index) = (0..entries.len()).find(|&index| matches(index)) else {
+            return step;
+        };
+        let message = if index > step {
+            format!("Function `{}` called before `{}`", entries[index].name, entries[step].name)
+        } else {
+            format!("Function `{}` called after `{}`", entries[index].name, entries[step - 1].name)
+        };
 
-    if !errors.is_empty() {
-        let mut error_message = String::from("Function missing required calls:\n");
-        
-        for error in &errors {
-            error_message.push_str(&format!(" - {}\n", error.message));
+        // Loops are walked several times, so the same site may be reported again.
+        let key = (site.id, message);
+        if !reported.contains(&key) {
+            errors.push(Error::new(key.1.clone(), site.span));
+            reported.push(key);
         }
+        step
+    };
 
-        return TokenStream::from(quote! {
-            compile_error!(#error_message);
-        }).into();
+    let scopes = TypeScopes::new(&function.sig, self_ty.map(type_name));
+    let exits = FlowWalker::new(transfer, scopes).walk_function(&function.block, 0);
+
+    // Report the first missing step of every path leaving the function.
+    for step in exits.into_iter().filter(|&step| step < entries.len()) {
+        let message = match step {
+            0 => format!("Function can return without calling `{}`", entries[step].name),
+            _ => format!(
+                "Function can return without calling `{}` after `{}`",
+                entries[step].name, entries[step - 1].name
+            ),
+        };
+        errors.push(Error::new(message, function.sig.ident.span()));
     }
 
-    TokenStream::from(quote! { #function }).into()
+    to_compile_errors(&errors)
 }
 
 
 #[derive(Debug)]
 struct Error {
     message: String,
+    span: Span,
 }
 
 impl Error {
-    fn new(message: String) -> Self {
-        Error { message }
+    fn new(message: String, span: Span) -> Self {
+        Error { message, span }
     }
 }
 
-fn check_whitelist(
-    name: &str, 
-    whitelist: &[String], 
-    _errors: &mut Vec<Error>, 
-    called_functions: &mut HashSet<String>
-) {
-    if whitelist.contains(&name.to_string()) {
-        called_functions.insert(name.to_string());
+/// Emits one spanned `compile_error!` per error, so that
+/// each offending call site is highlighted separately.
+fn to_compile_errors(errors: &[Error]) -> ProcTokenStream {
+    errors.iter()
+        .map(|e| syn::Error::new(e.span, &e.message))
+        .reduce(|mut acc, e| { acc.combine(e); acc })
+        .map(|e| e.to_compile_error())
+        .unwrap_or_default()
+}
+
+/// A whitelist entry such as `alloc`, `crate::mm::alloc`, `<Frame as Drop>::drop` or `log::info!`.
+///
+/// Unqualified entries match any function or method with that name. Qualified entries
+/// match a function call whose written path ends with all of the entry segments, so
+/// `mm::alloc` matches both `mm::alloc()` and `crate::mm::alloc()` but not `heap::alloc()`.
+/// Entries ending with `!` follow the same rules, but match macro invocations only.
+///
+/// For method calls the qualifier names the receiver type, e.g. `Vec::push` or `Self::flush`.
+/// It is checked whenever the receiver type is known syntactically, otherwise the method is
+/// matched by its name. Entries such as `self.frames.push` match the receiver expression itself.
+struct Entry {
+    name: String,
+    segments: Vec<String>,
+    /// The type in front of the method name, e.g. `Frame` for `<Frame as Drop>::drop`.
+    qualifier: Option<String>,
+    /// The receiver expression of `self.frames.push`, i.e. `self.frames`.
+    receiver: Option<String>,
+    is_macro: bool,
+    count: Option<Count>,
+}
+
+impl Entry {
+    fn parse(entry: &WhitelistEntry) -> Self {
+        let name = entry.name.as_str();
+        let is_macro = name.ends_with('!');
+        let mut path = name.trim_end_matches('!');
+
+        // Split `self.frames.push` into the receiver and the method name.
+        let mut receiver = None;
+        if let Some((base, method)) = path.rsplit_once('.').filter(|_| !is_macro) {
+            receiver = Some(base.replace(char::is_whitespace, ""));
+            path = method;
+        }
+
+        let (segments, qualifier) = match syn::parse_str::<ExprPath>(path) {
+            Ok(ExprPath { qself, path, .. }) => {
+                let segments = path_aliases(qself.as_ref(), &path).swap_remove(0);
+                let qualifier = match &qself {
+                    Some(qself) => Some(type_name(&qself.ty)),
+                    None => segments.iter().rev().nth(1).cloned(),
+                };
+                (segments, qualifier)
+            }
+            // Fall back to the plain segments if the entry is not a valid Rust path.
+            Err(_) => (path.split("::").map(|seg| seg.trim().to_string()).collect(), None),
+        };
+
+        Entry { name: name.to_string(), segments, qualifier, receiver, is_macro, count: entry.count }
+    }
+
+    /// Returns an error message if the number of found calls violates the entry.
+    fn check_count(&self, found: usize, found_label: &str) -> Option<String> {
+        match self.count {
+            Some(count) if !count.allows(found) => Some(format!(
+                "Function `{}` expected to be called {} times, found {} {}",
+                self.name, count, found, found_label
+            )),
+            None if found == 0 => Some(format!("Function missing required call: `{}` not called", self.name)),
+            _ => None,
+        }
     }
+
+    fn matches(&self, site: &CallSite) -> bool {
+        match site.kind {
+            CallKind::Macro if self.is_macro => {
+                site.paths.iter().any(|path| path.ends_with(&self.segments))
+            }
+            CallKind::Method if !self.is_macro => {
+                if site.paths[0].last() != self.segments.last() {
+                    return false;
+                }
+                match (&self.receiver, &self.qualifier) {
+                    (Some(receiver), _) => site.receiver_path.as_ref() == Some(receiver),
+                    (None, Some(qualifier)) => site.receiver_ty.admits(qualifier),
+                    (None, None) => true,
+                }
+            }
+            CallKind::Function if !self.is_macro && self.receiver.is_none() => {
+                site.paths.iter().any(|path| path.ends_with(&self.segments))
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CallKind {
+    Function,
+    /// Method calls carry the method name, and the receiver if it is known syntactically.
+    Method,
+    Macro,
+}
+
+/// A function call, method call or macro invocation found in the function body.
+pub struct CallSite {
+    /// Alternative spellings of the called path, the first being the written one.
+    /// `<Frame as Drop>::drop` is also known as `Drop::drop` and `Frame::drop`.
+    paths: Vec<Vec<String>>,
+    kind: CallKind,
+    /// The receiver of a method call, e.g. `self.frames` in `self.frames.push(frame)`.
+    receiver_path: Option<String>,
+    receiver_ty: ReceiverType,
+    pub span: Span,
+    /// The address of the call in the syntax tree, which tells call sites apart across walks.
+    pub id: usize,
 }
 
+impl CallSite {
+    /// Handle simple function calls, calls through other expressions are skipped.
+    pub fn from_call(call: &ExprCall) -> Option<Self> {
+        let Expr::Path(ExprPath { qself, path, .. }) = &*call.func else {
+            return None;
+        };
+
+        Some(CallSite {
+            paths: path_aliases(qself.as_ref(), path),
+            kind: CallKind::Function,
+            receiver_path: None,
+            receiver_ty: ReceiverType::Unknown,
+            span: call.span(),
+            id: call as *const ExprCall as usize,
+        })
+    }
+
+    /// Handle method calls type of instance.method_call().
+    pub fn from_method_call(call: &ExprMethodCall, scopes: &TypeScopes) -> Self {
+        CallSite {
+            paths: vec![vec![call.method.to_string()]],
+            kind: CallKind::Method,
+            receiver_path: receiver_path(&call.receiver),
+            receiver_ty: scopes.receiver_type(&call.receiver),
+            span: call.method.span(),
+            id: call as *const ExprMethodCall as usize,
+        }
+    }
+
+    /// Handle macro invocations, both in statement and expression position.
+    pub fn from_macro(mac: &Macro) -> Self {
+        CallSite {
+            paths: path_aliases(None, &mac.path),
+            kind: CallKind::Macro,
+            receiver_path: None,
+            receiver_ty: ReceiverType::Unknown,
+            span: mac.span(),
+            id: mac as *const Macro as usize,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        let name = self.paths[0].join("::");
+        match self.kind {
+            CallKind::Macro => format!("{}!", name),
+            _ => name,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self.kind {
+            CallKind::Macro => "Macro",
+            _ => "Function",
+        }
+    }
+
+    /// Tuple structs and enum variants such as `Some(x)` are built with the call syntax.
+    fn is_constructor(&self) -> bool {
+        self.kind == CallKind::Function && self.paths[0].last()
+            .and_then(|name| name.chars().next())
+            .is_some_and(char::is_uppercase)
+    }
+}
+
+/// Splits a (possibly qualified) path into its segments, generic arguments are dropped.
+fn path_aliases(qself: Option<&QSelf>, path: &syn::Path) -> Vec<Vec<String>> {
+    let segments: Vec<String> = path.segments.iter()
+        .map(|seg| seg.ident.to_string())
+        .collect();
+
+    let Some(qself) = qself else {
+        return vec![segments];
+    };
+
+    // `<Type as Trait>::method` keeps the trait path in front of the method name.
+    let type_name = type_name(&qself.ty);
+    let (trait_path, rest) = segments.split_at(qself.position);
+    let Some(trait_name) = trait_path.last() else {
+        return vec![[vec![type_name], rest.to_vec()].concat()];
+    };
+
+    vec![
+        [vec![format!("<{} as {}>", type_name, trait_name)], rest.to_vec()].concat(),
+        segments.clone(),
+        [vec![type_name], rest.to_vec()].concat(),
+    ]
+}
+
+/// Returns the last segment of a type path, looking through references.
+fn type_name(ty: &Type) -> String {
+    match ty {
+        Type::Path(type_path) => type_path.path.segments.last()
+            .map(|seg| seg.ident.to_string())
+            .unwrap_or_default(),
+        Type::Reference(reference) => type_name(&reference.elem),
+        Type::Paren(paren) => type_name(&paren.elem),
+        _ => quote! { #ty }.to_string().replace(' ', ""),
+    }
+}
 
 fn _print_ast<T>(item: &T, label: &str)
 where
@@ -73,111 +396,110 @@ where
     println!("{}: {}", label, item_string);
 }
 
-/// Recursively check all statements in a block.
-fn check_block_for_calls(
-    block: &Block, 
-    whitelist: &[String], 
-    errors: &mut Vec<Error>, 
-    called_functions: &mut HashSet<String>
-) {
-    for stmt in &block.stmts {
-        match stmt {
-            Stmt::Expr(expr, _) => {
-                // print_ast(expr, "Found Expression");
-                // Explore Nested Expression for new calls.
-                check_expr_for_calls(expr, whitelist, errors, called_functions);
+/// Walks every expression of the function body and records the found calls.
+struct CallVisitor<'a> {
+    whitelist: &'a [Entry],
+    errors: Vec<Error>,
+    /// The number of matching call sites, in the order of the whitelist entries.
+    call_counts: Vec<usize>,
+    mode: Mode,
+    scopes: TypeScopes,
+}
+
+impl CallVisitor<'_> {
+    fn check_whitelist(&mut self, site: CallSite) {
+        let mut is_whitelisted = false;
+
+        for (entry, count) in self.whitelist.iter().zip(self.call_counts.iter_mut()) {
+            if entry.matches(&site) {
+                *count += 1;
+                is_whitelisted = true;
             }
-            Stmt::Local(Local { init, .. }) => {
-                // Handle variable definitions.
-                if let Some(init) = init {
-                    // print_ast(&init.expr, "Found Initialization Expression");
-                    // Explore the local `let i = {__callsite__};` initialization.
-                    check_expr_for_calls(&init.expr, whitelist, errors, called_functions);
-                }
+        }
+
+        // Custom assertion based on whitelist data and found AST calls.
+        match self.mode {
+            Mode::Restricted if is_whitelisted => {
+                self.errors.push(Error::new(format!("{} `{}` is restricted by the denylist", site.label(), site.name()), site.span));
+            }
+            // Macro invocations and constructors are not calls on their own.
+            Mode::Only if !is_whitelisted && site.kind != CallKind::Macro && !site.is_constructor() => {
+                self.errors.push(Error::new(format!("Function `{}` is not whitelisted", site.name()), site.span));
             }
             _ => {}
         }
     }
 }
 
-/// NOTE: Assumes that Rustc lint will catch any repetative names between instances.
-fn check_expr_for_calls(
-    expr: &Expr, 
-    whitelist: &[String], 
-    errors: &mut Vec<Error>, 
-    called_functions: &mut HashSet<String>
-) {
-    match expr {
-        Expr::Call(ExprCall { func, .. }) => {
-            // Handle simple function calls.
-            if let Expr::Path(path) = &**func {
-                let func_name = path.path.segments.last()
-                .map(|seg| seg.ident.to_string());
-                
-                if let Some(func_name) = func_name {
-                    check_whitelist(
-                        &func_name, 
-                        whitelist, 
-                        errors, 
-                        called_functions
-                    );
-                }
-            }
-        }
-        
-        Expr::MethodCall(ExprMethodCall { method, .. }) => {
-            // Handle method calls type of instance.method_call().
-            let method_name = method.to_string();
-
-            check_whitelist(
-                &method_name, 
-                whitelist, 
-                errors, 
-                called_functions
-            );
-        }
-
-        Expr::Block(ExprBlock { block, .. }) => {
-            // Handle a block of code: `{ ... }`.
-            check_block_for_calls(block, whitelist, errors, called_functions);
-        }
-
-        Expr::If(ExprIf { then_branch, else_branch, .. }) => {
-            // Process the `then` block.
-            check_block_for_calls(&then_branch, whitelist, errors, called_functions);
-            // Process the `else` branch if present.
-            if let Some((_, else_expr)) = else_branch {
-                match &**else_expr {
-                    Expr::Block(ExprBlock { block, .. }) => {
-                        // Process the block inside `else_expr`
-                        check_block_for_calls(&block, whitelist, errors, called_functions);
-                    },
-                    // Handle other types of `else_expr` if necessary
-                    _ => check_expr_for_calls(expr, whitelist, errors, called_functions),
-                }
-            }
+/// NOTE: Every expression kind is explored through the default `Visit` traversal,
+/// read more via https://docs.rs/syn/latest/syn/visit/index.html.
+impl<'ast> Visit<'ast> for CallVisitor<'_> {
+    fn visit_expr_call(&mut self, call: &'ast ExprCall) {
+        if let Some(site) = CallSite::from_call(call) {
+            self.check_whitelist(site);
         }
+        // Explore the arguments for nested calls.
+        visit::visit_expr_call(self, call);
+    }
 
-        Expr::While(ExprWhile { body, .. }) => {
-            // Handle the expression inside the while loop (always block).
-            check_block_for_calls(&body, whitelist, errors, called_functions);
-        }
-        
-        Expr::ForLoop(ExprForLoop { body, .. }) => {
-            // Handle the expression inside the for loop (always block).
-            check_block_for_calls(&body, whitelist, errors, called_functions);
+    fn visit_expr_method_call(&mut self, call: &'ast ExprMethodCall) {
+        self.check_whitelist(CallSite::from_method_call(call, &self.scopes));
+        // Explore the receiver and the arguments for nested calls.
+        visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        self.check_whitelist(CallSite::from_macro(mac));
+        // Explore the macro arguments for nested calls.
+        for arg in parse_macro_args(mac) {
+            self.visit_expr(&arg);
         }
+    }
 
-        Expr::Closure(ExprClosure { body, .. }) => {
-            // Handle closures (either block or expression).
-            if let Expr::Block(ExprBlock { block, .. }) = &**body {
-                check_block_for_calls(block, whitelist, errors, called_functions);
-            } else {
-                check_expr_for_calls(body, whitelist, errors, called_functions);
-            }
+    fn visit_item(&mut self, _item: &'ast Item) {
+        // Nested items are not part of the function body, so their calls are skipped.
+    }
+
+    // The remaining methods keep track of the typed bindings in scope.
+
+    fn visit_block(&mut self, block: &'ast Block) {
+        self.scopes.push();
+        visit::visit_block(self, block);
+        self.scopes.pop();
+    }
+
+    fn visit_local(&mut self, local: &'ast Local) {
+        // The initializer is evaluated before the new bindings are introduced.
+        if let Some(init) = &local.init {
+            self.visit_local_init(init);
         }
-        
-        _ => {} // Handle other nested expression types if I missed anything important.
-        // Read more via https://jeltef.github.io/derive_more/syn/enum.Expr.html.
+        self.scopes.bind(&local.pat);
+    }
+
+    fn visit_arm(&mut self, arm: &'ast Arm) {
+        self.scopes.push();
+        self.scopes.bind(&arm.pat);
+        visit::visit_arm(self, arm);
+        self.scopes.pop();
+    }
+
+    fn visit_expr_closure(&mut self, closure: &'ast ExprClosure) {
+        self.scopes.push();
+        closure.inputs.iter().for_each(|input| self.scopes.bind(input));
+        visit::visit_expr_closure(self, closure);
+        self.scopes.pop();
+    }
+
+    fn visit_expr_for_loop(&mut self, for_loop: &'ast ExprForLoop) {
+        self.visit_expr(&for_loop.expr);
+        self.scopes.push();
+        self.scopes.bind(&for_loop.pat);
+        self.visit_block(&for_loop.body);
+        self.scopes.pop();
+    }
+
+    fn visit_expr_let(&mut self, expr: &'ast ExprLet) {
+        self.visit_expr(&expr.expr);
+        self.scopes.bind(&expr.pat);
     }
 }
@@ -0,0 +1,107 @@
+use syn::{Attribute, Item, ItemFn, ItemMod, Type};
+use proc_macro2::TokenStream as ProcTokenStream;
+use quote::quote;
+use crate::parser::whitelist::WhitelistArgs;
+use super::{items, mutates};
+
+/// The module attribute enforcing the field policies.
+const MACRO_NAME: &str = "enforce_mutations";
+
+/// The field attribute listing the functions allowed to mutate a field.
+const FIELD_ATTRIBUTE: &str = "mutated_only_by";
+
+/// A field that may only be mutated by the listed functions.
+struct FieldPolicy {
+    struct_name: String,
+    field: String,
+    functions: Vec<String>,
+}
+
+/// Enforces the `#[mutated_only_by(...)]` policies on the fields of the structs declared in a module.
+///
+/// The field attributes are collected and removed, then every function, method and default trait
+/// method of the module is checked as `#[nomutates]` does against the fields it is not listed for.
+/// Nested inline modules are part of the scope, unless they enforce their own policies.
+pub fn assert_field_policy_impl(mut item_mod: ItemMod) -> ProcTokenStream {
+    let mut errors = ProcTokenStream::new();
+    let mut policies: Vec<FieldPolicy> = Vec::new();
+    if let Some((_, items)) = &mut item_mod.content {
+        collect_policies(items, &mut policies, &mut errors);
+    }
+
+    let output = items::assert_item_impl(Item::Mod(item_mod), MACRO_NAME, |function, self_ty| {
+        check_function(&policies, function, self_ty)
+    });
+
+    quote! {
+        #output
+        #errors
+    }
+}
+
+/// Collects and strips the field policies of the structs in a module and its nested inline modules.
+fn collect_policies(items: &mut [Item], policies: &mut Vec<FieldPolicy>, errors: &mut ProcTokenStream) {
+    for item in items {
+        match item {
+            Item::Struct(item_struct) => {
+                let struct_name = item_struct.ident.to_string();
+                for (index, field) in item_struct.fields.iter_mut().enumerate() {
+                    let name = field.ident.as_ref()
+                        .map(|ident| ident.to_string())
+                        .unwrap_or_else(|| index.to_string());
+                    let (attrs, others): (Vec<Attribute>, Vec<Attribute>) = field.attrs.drain(..)
+                        .partition(|attr| attr.path().is_ident(FIELD_ATTRIBUTE));
+                    field.attrs = others;
+
+                    for attr in attrs {
+                        match attr.parse_args::<WhitelistArgs>() {
+                            Ok(functions) => policies.push(FieldPolicy {
+                                struct_name: struct_name.clone(),
+                                field: name.clone(),
+                                functions: functions.values,
+                            }),
+                            Err(err) => errors.extend(err.to_compile_error()),
+                        }
+                    }
+                }
+            }
+            // Modules enforcing their own policies are expanded separately.
+            Item::Mod(item_mod) if !items::has_override(&item_mod.attrs, MACRO_NAME) => {
+                if let Some((_, items)) = &mut item_mod.content {
+                    collect_policies(items, policies, errors);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn check_function(policies: &[FieldPolicy], function: &ItemFn, self_ty: Option<&Type>) -> ProcTokenStream {
+    let function_name = function.sig.ident.to_string();
+    let mut error_messages: Vec<String> = Vec::new();
+
+    for policy in policies {
+        if policy.functions.contains(&function_name) {
+            continue;
+        }
+        if mutates::mutates_field(function, self_ty, &policy.struct_name, &policy.field) {
+            let field = format!("`{}::{}`", policy.struct_name, policy.field);
+            let message = if policy.functions.is_empty() {
+                format!(" - Field {} may not be mutated by any function", field)
+            } else {
+                let allowed: Vec<String> = policy.functions.iter()
+                    .map(|name| format!("`{}`", name))
+                    .collect();
+                format!(" - Field {} may only be mutated by {}", field, allowed.join(", "))
+            };
+            error_messages.push(message);
+        }
+    }
+
+    if error_messages.is_empty() {
+        return ProcTokenStream::new();
+    }
+    let header = format!("Function `{}` contains mutations to restricted struct fields:\n", function_name);
+    let error_message = [header, error_messages.join("\n")].concat();
+    quote! { compile_error!(#error_message); }
+}
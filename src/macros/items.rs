@@ -0,0 +1,129 @@
+use syn::{
+    spanned::Spanned, Attribute, ImplItem, Item, ItemFn, ItemImpl,
+    ItemMod, ItemTrait, TraitItem, Type};
+use proc_macro2::TokenStream as ProcTokenStream;
+use quote::quote;
+
+/// Applies a function-level assertion to every function of an item.
+///
+/// Free functions are checked directly, impl blocks check each of their methods with the impl
+/// type as `Self`, traits check the methods with a default body, and inline modules check every
+/// function, impl and trait they contain. A function carrying its own `#[macro_name(...)]`
+/// attribute overrides the enclosing policy, so it is left to its own expansion.
+///
+/// The `check` closure returns the compile errors for one function, and is given the impl type
+/// when the function is a method. It may also add statements to the function body, e.g. compile-time
+/// references to the checked fields. The item is emitted with these bodies, followed by the errors.
+pub fn assert_item_impl<F>(mut item: Item, macro_name: &str, mut check: F) -> ProcTokenStream
+where
+    F: FnMut(&mut ItemFn, Option<&Type>) -> ProcTokenStream,
+{
+    let mut errors = ProcTokenStream::new();
+    check_item(&mut item, macro_name, &mut check, &mut errors);
+
+    quote! {
+        #item
+        #errors
+    }
+}
+
+fn check_item<F>(item: &mut Item, macro_name: &str, check: &mut F, errors: &mut ProcTokenStream)
+where
+    F: FnMut(&mut ItemFn, Option<&Type>) -> ProcTokenStream,
+{
+    let span = item.span();
+    match item {
+        Item::Fn(function) => errors.extend(check(function, None)),
+        Item::Impl(item_impl) => check_impl(item_impl, macro_name, check, errors),
+        Item::Trait(item_trait) => check_trait(item_trait, macro_name, check, errors),
+        Item::Mod(item_mod) => check_mod(item_mod, macro_name, check, errors),
+        _ => errors.extend(syn::Error::new(
+            span,
+            format!("`{}` can only be applied to functions, impl blocks, traits and inline modules", macro_name),
+        ).to_compile_error()),
+    }
+}
+
+fn check_impl<F>(item_impl: &mut ItemImpl, macro_name: &str, check: &mut F, errors: &mut ProcTokenStream)
+where
+    F: FnMut(&mut ItemFn, Option<&Type>) -> ProcTokenStream,
+{
+    for impl_item in &mut item_impl.items {
+        let ImplItem::Fn(method) = impl_item else {
+            continue;
+        };
+        if has_override(&method.attrs, macro_name) {
+            continue;
+        }
+
+        let mut function = ItemFn {
+            attrs: method.attrs.clone(),
+            vis: method.vis.clone(),
+            sig: method.sig.clone(),
+            block: Box::new(method.block.clone()),
+        };
+        errors.extend(check(&mut function, Some(&item_impl.self_ty)));
+        method.block = *function.block;
+    }
+}
+
+fn check_trait<F>(item_trait: &mut ItemTrait, macro_name: &str, check: &mut F, errors: &mut ProcTokenStream)
+where
+    F: FnMut(&mut ItemFn, Option<&Type>) -> ProcTokenStream,
+{
+    for trait_item in &mut item_trait.items {
+        // Only the methods with a default body can be checked.
+        let TraitItem::Fn(method) = trait_item else {
+            continue;
+        };
+        let Some(block) = &mut method.default else {
+            continue;
+        };
+        if has_override(&method.attrs, macro_name) {
+            continue;
+        }
+
+        let mut function = ItemFn {
+            attrs: method.attrs.clone(),
+            vis: syn::Visibility::Inherited,
+            sig: method.sig.clone(),
+            block: Box::new(block.clone()),
+        };
+        errors.extend(check(&mut function, None));
+        *block = *function.block;
+    }
+}
+
+fn check_mod<F>(item_mod: &mut ItemMod, macro_name: &str, check: &mut F, errors: &mut ProcTokenStream)
+where
+    F: FnMut(&mut ItemFn, Option<&Type>) -> ProcTokenStream,
+{
+    let span = item_mod.span();
+    let Some((_, items)) = &mut item_mod.content else {
+        errors.extend(syn::Error::new(
+            span,
+            format!("`{}` can only be applied to inline modules", macro_name),
+        ).to_compile_error());
+        return;
+    };
+
+    for item in items {
+        match item {
+            Item::Fn(function) if has_override(&function.attrs, macro_name) => {}
+            Item::Impl(item_impl) if has_override(&item_impl.attrs, macro_name) => {}
+            Item::Trait(item_trait) if has_override(&item_trait.attrs, macro_name) => {}
+            Item::Mod(item_mod) if has_override(&item_mod.attrs, macro_name) => {}
+            Item::Fn(_) | Item::Impl(_) | Item::Trait(_) => check_item(item, macro_name, check, errors),
+            // Nested out-of-line modules are out of reach, unlike inline ones.
+            Item::Mod(nested) if nested.content.is_some() => check_item(item, macro_name, check, errors),
+            _ => {}
+        }
+    }
+}
+
+/// Checks if an item carries its own `#[macro_name(...)]` attribute.
+pub fn has_override(attrs: &[Attribute], macro_name: &str) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().segments.last().is_some_and(|seg| seg.ident == macro_name)
+    })
+}
@@ -1,5 +1,10 @@
 pub mod calls;
+pub mod call_flow;
+pub mod call_types;
 pub mod mutates;
 pub mod consumes;
 pub mod size_align;
 pub mod private_fields;
+pub mod items;
+pub mod field_policy;
+pub mod policies;
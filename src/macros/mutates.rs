@@ -1,142 +1,743 @@
 use syn::{
+    visit::Visit, visit_mut::{self, VisitMut},
     Block, Local, ExprField, ExprClosure, ExprBlock, TypeReference,
     FnArg, ExprPath, ExprIf, ExprWhile, ExprForLoop,
     punctuated::Punctuated, token::Comma, Expr, Member,
-    ItemFn, Pat, Type, Stmt, TypePath, ExprCall, PatIdent};
+    ItemFn, Pat, Type, Stmt, TypePath, ExprCall, PatIdent,
+    BinOp, ExprUnary, UnOp, Path, PathArguments, GenericArgument,
+    PatStruct, PatTupleStruct, PatType, ExprStruct, ExprMatch,
+    ExprUnsafe, ExprMethodCall, Macro, PointerMutability, TypePtr, ExprAsync,
+    ExprConst, ExprTryBlock, ExprLoop, Ident, parse_quote};
 use proc_macro2::TokenStream as ProcTokenStream;
 use crate::field_whitelist::WhitelistArgs;
-use std::collections::HashSet;
+use super::call_types::IdentCollector;
+use crate::parser::macro_args::parse_macro_args;
+use std::collections::HashMap;
 use quote::quote;
 
+/// Tracked bindings visible at a program point, the innermost scope last.
+///
+/// Each binding maps to the field path it refers to: an empty path for instances of the struct,
+/// or e.g. `count` for `count` destructured from `MyStruct { count, .. }` or borrowed as
+/// `&mut self.count`. Untracked bindings shadow outer ones with `None`.
+struct Instances {
+    scopes: Vec<HashMap<String, Option<String>>>,
+}
+
+impl Instances {
+    fn new() -> Self {
+        Instances { scopes: vec![HashMap::new()] }
+    }
+
+    fn push(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn pop(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn insert(&mut self, name: String, path: String) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, Some(path));
+        }
+    }
+
+    /// Hides the outer bindings named by a pattern, e.g. `let self_ref = 0;`.
+    fn shadow(&mut self, pat: &Pat) {
+        let mut collector = IdentCollector(Vec::new());
+        collector.visit_pat(pat);
+        if let Some(scope) = self.scopes.last_mut() {
+            for ident in collector.0 {
+                scope.insert(ident, None);
+            }
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&String> {
+        self.scopes.iter().rev()
+            .find_map(|scope| scope.get(name))
+            .and_then(|path| path.as_ref())
+    }
+
+    fn remove(&mut self, name: &str) {
+        for scope in &mut self.scopes {
+            scope.remove(name);
+        }
+    }
+
+    /// Checks if any binding other than `self` is tracked.
+    fn has_arguments(&self) -> bool {
+        self.scopes.iter()
+            .flat_map(|scope| scope.iter())
+            .any(|(name, path)| name != "self" && path.is_some())
+    }
+}
+
+/// The struct whose fields are checked.
+struct Target<'a> {
+    name: &'a str,
+    /// The impl type, when the macro is applied to a whole impl block.
+    self_ty: Option<&'a Type>,
+    /// Extra interior mutability methods listed in the attribute.
+    interior_methods: &'a [String],
+}
+
+impl Target<'_> {
+    /// Checks if a written type is the struct, regardless of its module path and generic
+    /// arguments, and looking through references and wrappers, e.g. `&mut Box<mm::MyStruct<T>>`.
+    fn matches_type(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Reference(reference) => self.matches_type(&reference.elem),
+            Type::Paren(paren) => self.matches_type(&paren.elem),
+            Type::Group(group) => self.matches_type(&group.elem),
+            Type::Path(TypePath { qself: None, path }) => {
+                let Some(last) = path.segments.last() else {
+                    return false;
+                };
+                if WRAPPER_TYPES.contains(&last.ident.to_string().as_str()) {
+                    if let PathArguments::AngleBracketed(args) = &last.arguments {
+                        return args.args.iter().any(|arg| {
+                            matches!(arg, GenericArgument::Type(inner) if self.matches_type(inner))
+                        });
+                    }
+                }
+                self.matches_path(path)
+            }
+            _ => false,
+        }
+    }
+
+    /// Checks if calling a method on a field mutates it, in place or through interior mutability.
+    /// `replace` takes one argument on `Option`, `Cell` and `RefCell`, but two on `str`.
+    fn is_mutating_method(&self, call: &ExprMethodCall) -> bool {
+        let method = call.method.to_string();
+        MUTATING_METHODS.contains(&method.as_str())
+            || INTERIOR_METHODS.contains(&method.as_str())
+            || (method == "replace" && call.args.len() == 1)
+            || self.interior_methods.contains(&method)
+    }
+
+    /// Checks if a path names the struct, e.g. `MyStruct`, `crate::mm::MyStruct<T>` or `Self`.
+    fn matches_path(&self, path: &Path) -> bool {
+        match path.segments.last() {
+            Some(last) if last.ident == "Self" => {
+                // `Self` is only known from an enclosing impl block, otherwise it is assumed
+                // to be the struct, just like `self` is assumed to be an instance.
+                let self_target = Target { self_ty: None, ..*self };
+                self.self_ty.is_none_or(|self_ty| self_target.matches_type(self_ty))
+            }
+            Some(last) => last.ident == self.name,
+            None => false,
+        }
+    }
+}
+
+/// Smart pointers, wrappers and lock guards giving access to the struct inside.
+const WRAPPER_TYPES: &[&str] = &[
+    "Box", "Rc", "Arc", "Pin", "Option", "ManuallyDrop", "Cell", "RefCell", "RefMut",
+    "Mutex", "MutexGuard", "RwLock", "RwLockWriteGuard", "UnsafeCell", "NonNull",
+];
+
+/// Methods borrowing the wrapped struct, e.g. `shared.lock().unwrap().count = 0`.
+const ACCESS_METHODS: &[&str] = &[
+    "lock", "try_lock", "write", "try_write", "borrow_mut", "try_borrow_mut", "get_mut",
+    "as_mut", "as_deref_mut", "deref_mut", "as_mut_ptr",
+];
+
+/// Methods unwrapping the borrow returned by an access method. On a field itself,
+/// e.g. `self.option.unwrap()`, they copy or move the value out instead.
+const UNWRAP_METHODS: &[&str] = &["unwrap", "expect"];
+
+/// Whitelists every field, including overwrites of the whole instance such as `*self = ..`.
+const WILDCARD: &str = "*";
 
-pub fn assert_mutate_impl(macro_data: &WhitelistArgs, function: &ItemFn, restricted_mode: bool) -> ProcTokenStream {
-    // NOTE: For better code layout, we will require separate proc-macro for each field
-    // to be whitelisted type of #[struct_name: (field1, field2, field3, ...)].
-    
-    let struct_name = &macro_data.struct_name;
-    let whitelist = &macro_data.values;
+/// Methods of the standard collections, `Option` and the integer types that take
+/// `&mut self` and modify the value in place, e.g. `self.frames.push(frame)`. `replace`
+/// is told apart from `str::replace` by its arguments in `Target::is_mutating_method`.
+const MUTATING_METHODS: &[&str] = &[
+    "push", "push_str", "push_back", "push_front", "pop", "pop_back", "pop_front",
+    "insert", "remove", "swap_remove", "clear", "truncate", "extend", "extend_from_slice",
+    "append", "drain", "retain", "retain_mut", "dedup", "dedup_by_key", "resize", "split_off",
+    "sort", "sort_by", "sort_by_key", "sort_unstable", "sort_unstable_by", "reverse",
+    "fill", "swap", "rotate_left", "rotate_right", "copy_from_slice", "clone_from_slice",
+    "take", "get_or_insert", "get_or_insert_with", "insert_str", "set_len", "reserve",
+];
 
+/// Methods of `Cell`, `RefCell`, `OnceCell`, `Mutex`, `RwLock`, the atomics and `UnsafeCell`
+/// changing the value behind a shared reference, e.g. `self.count.set(0)` or `self.state.lock()`.
+const INTERIOR_METHODS: &[&str] = &[
+    "set", "update", "replace_with", "borrow_mut", "try_borrow_mut", "get_or_init", "get_mut",
+    "lock", "try_lock", "write", "try_write", "store", "compare_exchange", "compare_exchange_weak",
+    "fetch_add", "fetch_sub", "fetch_and", "fetch_nand", "fetch_or", "fetch_xor",
+    "fetch_max", "fetch_min", "fetch_update", "raw_get",
+];
+
+/// Methods iterating over references to the elements of a collection.
+const ITER_METHODS: &[&str] = &[
+    "iter", "iter_mut", "values", "values_mut", "chunks_mut", "chunks_exact_mut",
+];
+
+/// Iterator adapters yielding the same elements.
+const ITER_ADAPTERS: &[&str] = &[
+    "rev", "skip", "take", "step_by", "filter", "skip_while", "take_while", "peekable", "by_ref", "into_iter",
+];
+
+/// Functions writing through their `&mut` or raw pointer argument, as `(module, function)`.
+const WRITING_FUNCTIONS: &[(&str, &str)] = &[
+    ("mem", "swap"), ("mem", "replace"), ("mem", "take"),
+    ("ptr", "write"), ("ptr", "write_volatile"), ("ptr", "write_unaligned"), ("ptr", "swap"),
+];
+
+
+/// Checks the field mutations of a function against the whitelist, and returns the compile errors.
+/// The impl type of a method, if any, resolves `Self` and tells whether `self` is an instance.
+pub fn assert_mutate_impl(
+    macro_data: &WhitelistArgs,
+    function: &ItemFn,
+    self_ty: Option<&Type>,
+    restricted_mode: bool,
+) -> ProcTokenStream {
     let mut errors: Vec<Error> = Vec::new();
     let inputs: &Punctuated<FnArg, Comma> = &function.sig.inputs;
-    let block: &Box<Block> = &function.block;
+    let block: &Block = &function.block;
 
-    // Track found instances for further mutation checks.
-    let mut found_instances = HashSet::new();
-    // Entry point: figure out the instance name by exploring the function input arguments.
+    // Entry point: figure out the instance names by exploring the function input arguments.
     // If not found, then need to parse the function body for the inner declaration check.
-    extract_instance_names(inputs, struct_name, &mut found_instances);
+    let mut instances: Vec<Instances> = macro_data.structs.iter()
+        .map(|struct_whitelist| {
+            let target = Target {
+                name: &struct_whitelist.struct_name,
+                self_ty,
+                interior_methods: &macro_data.interior_methods,
+            };
+            let mut found_instances = Instances::new();
+            extract_instance_names(inputs, &target, &mut found_instances);
+            found_instances
+        })
+        .collect();
 
-    // Parse function recursively and as a state machine
-    // extract new definitions on the way if needed.
-    check_block_for_mutation(
-        block, 
-        whitelist,
-        &mut found_instances, 
-        struct_name, 
-        &mut errors,
-        restricted_mode);
+    // Without the impl type, `self` is ambiguous when several structs are listed. It then
+    // belongs to the structs not passed as another argument, e.g. `fn alloc(&mut self, frame: &mut Frame)`.
+    if self_ty.is_none() && instances.len() > 1 {
+        let is_argument: Vec<bool> = instances.iter()
+            .map(|found_instances| found_instances.has_arguments())
+            .collect();
+        if is_argument.contains(&false) {
+            for (found_instances, is_argument) in instances.iter_mut().zip(is_argument) {
+                if is_argument {
+                    found_instances.remove("self");
+                }
+            }
+        }
+
+        // Without any of them as an argument, the fields written through `self` cannot be told apart.
+        let owners: Vec<&str> = macro_data.structs.iter().zip(&instances)
+            .filter(|(_, found_instances)| found_instances.get("self").is_some())
+            .map(|(struct_whitelist, _)| struct_whitelist.struct_name.as_str())
+            .collect();
+        if let (Some(receiver), true) = (function.sig.receiver(), owners.len() > 1) {
+            let message = format!(
+                "`self` may be any of `{}`, apply the attribute to the impl block so that its type is known",
+                owners.join("`, `")
+            );
+            return syn::Error::new_spanned(receiver, message).to_compile_error();
+        }
+    }
+
+    // Every struct is checked separately, with its own instances and whitelist.
+    for (struct_whitelist, mut found_instances) in macro_data.structs.iter().zip(instances) {
+        let target = Target {
+            name: &struct_whitelist.struct_name,
+            self_ty,
+            interior_methods: &macro_data.interior_methods,
+        };
+        let whitelist = &struct_whitelist.values;
 
+        // Parse function recursively and as a state machine
+        // extract new definitions on the way if needed.
+        check_block_for_mutation(
+            block, 
+            whitelist,
+            &mut found_instances, 
+            &target, 
+            &mut errors,
+            restricted_mode);
+    }
 
     if !errors.is_empty() {
         // Construct the error message based on each isntance of the struct_name.
         let header = "Function contains mutations to non-whitelisted struct fields:\n";
-        let error_messages: Vec<String> = errors.iter()
-            .map(|e| format!(" - {}", e.message))
-            .collect();
+        let mut error_messages: Vec<String> = Vec::new();
+        for e in &errors {
+            // The same field may be reached through an alias and its borrow.
+            let message = format!(" - {}", e.message);
+            if !error_messages.contains(&message) {
+                error_messages.push(message);
+            }
+        }
         let error_message = [header, &error_messages.join("\n")].concat();
 
-        let tokens = quote! { compile_error!(#error_message); };
-        return tokens.into();
+        // Point at the function, as the attribute may come from a policy or an enclosing item.
+        return syn::Error::new(function.sig.ident.span(), error_message).to_compile_error();
+    }
+
+    ProcTokenStream::new()
+}
+
+/// Adds a compile-time reference to every listed field at the start of the function body, e.g.
+/// `let _ = |__instance: &MyStruct| { let _ = &__instance.count; };`, so that a misspelled or
+/// renamed field fails with rustc's own "no field" error. Only the first field of a nested path is
+/// checked, e.g. `child` in `child.value`, since the rest may be reached through an `Option` or a lock.
+/// Instances built in the body are referenced right after their `let` instead, e.g. `let _ = &frame.count;`,
+/// where their type is inferred. Returns the errors for paths that are not fields at all.
+pub fn reference_fields(macro_data: &WhitelistArgs, function: &mut ItemFn, self_ty: Option<&Type>) -> ProcTokenStream {
+    let mut errors = ProcTokenStream::new();
+    let mut stmts: Vec<Stmt> = Vec::new();
+
+    for struct_whitelist in &macro_data.structs {
+        let target = Target { name: &struct_whitelist.struct_name, self_ty, interior_methods: &[] };
+        let mut members: Vec<Member> = Vec::new();
+        for field in &struct_whitelist.values {
+            let name = field.split(['.', '[']).next().unwrap_or_default();
+            if field == WILDCARD {
+                continue;
+            }
+            match syn::parse_str::<Member>(name) {
+                Ok(member) => members.push(member),
+                Err(_) => {
+                    let message = format!("`{}` is not a field path of `{}`", field, struct_whitelist.struct_name);
+                    errors.extend(quote! { compile_error!(#message); });
+                }
+            }
+        }
+
+        if members.is_empty() {
+            continue;
+        }
+        // The struct may not be nameable here, e.g. a generic struct or one out of scope by name.
+        if let Some(ty) = instance_type(&target, &function.sig.inputs) {
+            stmts.push(parse_quote! {
+                let _ = |__instance: &#ty| { #(let _ = &__instance.#members;)* };
+            });
+        }
+        LocalFieldReferences { target: &target, members: &members }.visit_block_mut(&mut function.block);
+    }
+
+    function.block.stmts.splice(0..0, stmts);
+    errors
+}
+
+/// Names the struct inside the function body: `Self` in its own impl block, or the type of an
+/// argument holding it, e.g. `MyStruct<T>` for `&mut MyStruct<T>`.
+fn instance_type(target: &Target, inputs: &Punctuated<FnArg, Comma>) -> Option<Type> {
+    if target.self_ty.is_some_and(|self_ty| struct_type(self_ty, target).is_some()) {
+        return Some(parse_quote!(Self));
+    }
+    inputs.iter().find_map(|input| match input {
+        FnArg::Typed(pat_type) => struct_type(&pat_type.ty, target).cloned(),
+        FnArg::Receiver(_) => None,
+    })
+}
+
+/// References the listed fields after every `let` binding a new instance of the struct.
+struct LocalFieldReferences<'a> {
+    target: &'a Target<'a>,
+    members: &'a [Member],
+}
+
+impl VisitMut for LocalFieldReferences<'_> {
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        visit_mut::visit_block_mut(self, block);
+        let mut stmts: Vec<Stmt> = Vec::with_capacity(block.stmts.len());
+        for stmt in block.stmts.drain(..) {
+            let instance = match &stmt {
+                Stmt::Local(local) => local_instance(local, self.target),
+                _ => None,
+            };
+            stmts.push(stmt);
+            if let Some(instance) = instance {
+                let members = self.members;
+                stmts.push(parse_quote! { #(let _ = &#instance.#members;)* });
+            }
+        }
+        block.stmts = stmts;
     }
+}
 
-    // Return the original function if no errors.
-    let output = quote! { #function };
-    output.into()
+/// Finds the binding of a `let` certainly holding the struct: one initialized with a struct
+/// literal, e.g. `let frame = Frame { .. };`, or declared with its type, e.g. `let frame: &mut Frame = ..;`.
+/// Associated functions are left out, as `Frame::builder()` may return another type.
+fn local_instance(local: &Local, target: &Target) -> Option<Ident> {
+    let init = local.init.as_ref()?;
+    match &local.pat {
+        Pat::Ident(PatIdent { ident, subpat: None, .. }) if is_struct_literal(&init.expr, target) => Some(ident.clone()),
+        Pat::Type(PatType { pat, ty, .. }) if is_struct_type(ty, target) => match &**pat {
+            Pat::Ident(PatIdent { ident, subpat: None, .. }) => Some(ident.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Checks if an expression is a literal of the struct, possibly chosen by `if` or `match`.
+/// `Self { .. }` only counts when the impl type is known.
+fn is_struct_literal(expr: &Expr, target: &Target) -> bool {
+    match expr {
+        Expr::Struct(ExprStruct { qself: None, path, .. }) => {
+            target.matches_path(path) && (target.self_ty.is_some() || !path.is_ident("Self"))
+        }
+        Expr::Paren(paren) => is_struct_literal(&paren.expr, target),
+        Expr::Block(ExprBlock { block, .. }) => block_value(block).is_some_and(|expr| is_struct_literal(expr, target)),
+        Expr::If(ExprIf { then_branch, else_branch, .. }) => {
+            block_value(then_branch).is_some_and(|expr| is_struct_literal(expr, target))
+                || else_branch.as_ref().is_some_and(|(_, expr)| is_struct_literal(expr, target))
+        }
+        Expr::Match(ExprMatch { arms, .. }) => arms.iter().any(|arm| is_struct_literal(&arm.body, target)),
+        _ => false,
+    }
+}
+
+/// Checks if a written type is the struct itself or a reference to it.
+fn is_struct_type(ty: &Type, target: &Target) -> bool {
+    match ty {
+        Type::Reference(reference) => is_struct_type(&reference.elem, target),
+        Type::Paren(paren) => is_struct_type(&paren.elem, target),
+        Type::Path(TypePath { qself: None, path }) => {
+            path.segments.last().is_some_and(|last| last.ident == target.name)
+        }
+        _ => false,
+    }
+}
+
+/// Finds the struct behind references and wrappers in a written type, as `Target::matches_type` does.
+fn struct_type<'a>(ty: &'a Type, target: &Target) -> Option<&'a Type> {
+    match ty {
+        Type::Reference(reference) => struct_type(&reference.elem, target),
+        Type::Paren(paren) => struct_type(&paren.elem, target),
+        Type::Group(group) => struct_type(&group.elem, target),
+        Type::Path(TypePath { qself: None, path }) => {
+            let last = path.segments.last()?;
+            if last.ident == target.name {
+                return Some(ty);
+            }
+            if !WRAPPER_TYPES.contains(&last.ident.to_string().as_str()) {
+                return None;
+            }
+            let PathArguments::AngleBracketed(args) = &last.arguments else {
+                return None;
+            };
+            args.args.iter().find_map(|arg| match arg {
+                GenericArgument::Type(inner) => struct_type(inner, target),
+                _ => None,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Checks if a function mutates the field at `field` of a struct, in the same way as `#[nomutates]`:
+/// writes nested inside the field, overwrites of the whole instance and mutable raw pointers count too.
+pub fn mutates_field(function: &ItemFn, self_ty: Option<&Type>, struct_name: &str, field: &str) -> bool {
+    let target = Target { name: struct_name, self_ty, interior_methods: &[] };
+    let mut found_instances = Instances::new();
+    extract_instance_names(&function.sig.inputs, &target, &mut found_instances);
+
+    let mut errors: Vec<Error> = Vec::new();
+    check_block_for_mutation(
+        &function.block,
+        &[field.to_string()],
+        &mut found_instances,
+        &target,
+        &mut errors,
+        true);
+    !errors.is_empty()
 }
 
 /// Extracts all instance names from given function 
 /// arguments if matches the specified struct_name.
 fn extract_instance_names(
     inputs: &Punctuated<FnArg, Comma>,
-    struct_name: &str,
-    found_instances: &mut HashSet<String>
+    target: &Target,
+    found_instances: &mut Instances,
 ) {
     for arg in inputs {
         match arg {
             FnArg::Typed(pat_type) => {
-                let pat = &*pat_type.pat;
-                let ty = &*pat_type.ty;
-                let ty_str = quote! { #ty }.to_string();
-
-                match pat {
-                    Pat::Ident(pat_ident) => {
-                        if let Type::Reference(TypeReference { elem, .. }) = ty {
-                            // Handle type reference by dereferencing.
-                            if let Type::Path(TypePath { path, .. }) = &**elem {
-                                // let path_str = quote! { #path }.to_string();
-                                if path.is_ident(struct_name) {
-                                    // println!("Added type path (dereferenced): {}", path_str);
-                                    found_instances.insert(pat_ident.ident.to_string());
-                                }
-                            }
-                        } else if let Type::Path(TypePath { path, .. }) = ty {
-                            // let path_str = quote! { #path }.to_string();
-                            if path.is_ident(struct_name) {
-                                // println!("Added type path: {}", path_str);
-                                found_instances.insert(pat_ident.ident.to_string());
-                            }
-                        } else {
-                            panic!("Found unexpected argument type: {}. We apologize for the inconvenience.
-                            Please report this issue on GitHub, and we will address it promptly.", ty_str);
-                        }
-                    }
-                    _ => {
-                        let pat_str = quote! { #pat }.to_string();
-                        panic!("Found unexpected pattern: {}. We apologize for the inconvenience. 
-                        Please report this issue on GitHub, and we will address it promptly.", pat_str);
-                    }
+                // Arguments of unrelated types, `impl Trait` or slices are simply not tracked.
+                bind_instance(&pat_type.pat, &pat_type.ty, target, found_instances);
+            }
+
+            FnArg::Receiver(_) => {
+                // Handle the case where the argument is `self` for methods, unless the impl type
+                // is known to be another struct. Even `&self` may mutate through interior mutability.
+                if target.self_ty.is_none_or(|self_ty| target.matches_type(self_ty)) {
+                    found_instances.insert("self".to_string(), String::new());
                 }
             }
+        }
+    }
+}
+
+/// Tracks the bindings of a pattern matched against a value of the given type,
+/// e.g. `instance: &mut MyStruct`, `(left, right): (MyStruct, MyStruct)` or
+/// `MyStruct { count, .. }: &mut MyStruct`, where `count` then refers to the field.
+fn bind_instance(pat: &Pat, ty: &Type, target: &Target, found_instances: &mut Instances) {
+    match (pat, ty) {
+        // Look through references, the fields are written through them.
+        (_, Type::Reference(TypeReference { elem, .. })) => bind_instance(pat, elem, target, found_instances),
+        (_, Type::Paren(paren)) => bind_instance(pat, &paren.elem, target, found_instances),
+        (_, Type::Group(group)) => bind_instance(pat, &group.elem, target, found_instances),
+        (Pat::Reference(reference), _) => bind_instance(&reference.pat, ty, target, found_instances),
+        (Pat::Paren(paren), _) => bind_instance(&paren.pat, ty, target, found_instances),
+
+        (Pat::Ident(pat_ident), _) if target.matches_type(ty) => {
+            found_instances.insert(pat_ident.ident.to_string(), String::new());
+            if let Some((_, subpat)) = &pat_ident.subpat {
+                bind_fields(subpat, "", false, found_instances);
+            }
+        }
+
+        (Pat::Struct(PatStruct { path, .. }) | Pat::TupleStruct(PatTupleStruct { path, .. }), _)
+            if target.matches_path(path) => bind_fields(pat, "", false, found_instances),
 
-            FnArg::Receiver(receiver) => {
-                // Handle the case where the argument is `self` for methods
-                if receiver.reference.is_some() || receiver.mutability.is_some() {
-                    found_instances.insert("self".to_string());
+        (Pat::Tuple(pat_tuple), Type::Tuple(type_tuple)) => {
+            // Elements after a `..` rest pattern are matched from the end of the tuple.
+            let rest = pat_tuple.elems.iter().position(|elem| matches!(elem, Pat::Rest(_)));
+            let front = rest.unwrap_or(pat_tuple.elems.len());
+            for (elem, ty) in pat_tuple.elems.iter().take(front).zip(&type_tuple.elems) {
+                bind_instance(elem, ty, target, found_instances);
+            }
+            if rest.is_some() {
+                let back = pat_tuple.elems.iter().skip(front + 1).rev();
+                for (elem, ty) in back.zip(type_tuple.elems.iter().rev()) {
+                    bind_instance(elem, ty, target, found_instances);
                 }
             }
         }
+
+        _ => {}
+    }
+}
+
+/// Tracks the bindings of a pattern destructuring the field at `path` of an instance,
+/// or the instance itself for an empty path. When the value is matched by place rather
+/// than through a reference, only `ref` and `ref mut` bindings refer to the field.
+fn bind_fields(pat: &Pat, path: &str, by_ref_only: bool, found_instances: &mut Instances) {
+    match pat {
+        Pat::Ident(pat_ident) => {
+            if !by_ref_only || pat_ident.by_ref.is_some() {
+                found_instances.insert(pat_ident.ident.to_string(), path.to_string());
+            }
+            if let Some((_, subpat)) = &pat_ident.subpat {
+                bind_fields(subpat, path, by_ref_only, found_instances);
+            }
+        }
+        // The payload of an enum variant such as `Some(frame)` or `State::Busy { owner }`
+        // is not a field, so its bindings refer to the matched place as a whole.
+        Pat::Struct(pat_struct) if is_variant(&pat_struct.path) => {
+            for field in &pat_struct.fields {
+                bind_fields(&field.pat, path, by_ref_only, found_instances);
+            }
+        }
+        Pat::TupleStruct(pat_tuple) if is_variant(&pat_tuple.path) => {
+            for elem in &pat_tuple.elems {
+                bind_fields(elem, path, by_ref_only, found_instances);
+            }
+        }
+        Pat::Struct(pat_struct) => {
+            for field in &pat_struct.fields {
+                bind_fields(&field.pat, &join_path(path, &member_name(&field.member)), by_ref_only, found_instances);
+            }
+        }
+        Pat::TupleStruct(pat_tuple) => bind_elements(&pat_tuple.elems, path, by_ref_only, found_instances),
+        Pat::Type(pat_type) => bind_fields(&pat_type.pat, path, by_ref_only, found_instances),
+        Pat::Tuple(pat_tuple) => bind_elements(&pat_tuple.elems, path, by_ref_only, found_instances),
+        Pat::Slice(pat_slice) => {
+            for elem in &pat_slice.elems {
+                bind_fields(elem, &format!("{}[]", path), by_ref_only, found_instances);
+            }
+        }
+        // Bindings behind a `&` pattern are copied out, unless they are `ref` bindings.
+        Pat::Reference(reference) => bind_fields(&reference.pat, path, true, found_instances),
+        Pat::Paren(paren) => bind_fields(&paren.pat, path, by_ref_only, found_instances),
+        Pat::Or(pat_or) => {
+            for case in &pat_or.cases {
+                bind_fields(case, path, by_ref_only, found_instances);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Checks if a pattern path names an enum variant rather than a struct, i.e. one of
+/// `Some`, `Ok` and `Err`, or a qualified path such as `State::Busy`.
+fn is_variant(path: &Path) -> bool {
+    path.segments.len() > 1
+        || path.get_ident().is_some_and(|ident| ident == "Some" || ident == "Ok" || ident == "Err")
+}
+
+/// Binds a pattern matched against a value in the innermost scope. The new bindings shadow
+/// the outer ones, and refer to a field if the value is borrowed from an instance, e.g.
+/// `let r = &mut self.field;`, or if they are `ref` bindings into a field, e.g.
+/// `if let Some(ref mut value) = self.option`.
+fn bind_pattern(pat: &Pat, value: Option<&Expr>, found_instances: &mut Instances) {
+    let alias = value.and_then(|value| alias_path(value, found_instances));
+    let place = value.and_then(|value| place_path(value, found_instances));
+    found_instances.shadow(pat);
+    match (alias, place) {
+        (Some(path), _) => bind_fields(pat, &path, false, found_instances),
+        (None, Some(path)) => bind_fields(pat, &path, true, found_instances),
+        (None, None) => {}
+    }
+}
+
+/// Binds the pattern of a `for` loop over the elements of a field, e.g. `entry` in
+/// `for entry in self.entries.iter_mut()` refers to `entries[]`.
+fn bind_loop_pattern(pat: &Pat, iter: &Expr, found_instances: &mut Instances) {
+    let element = element_path(iter, found_instances);
+    found_instances.shadow(pat);
+    match (element, pat) {
+        // `for (index, entry) in self.entries.iter_mut().enumerate()`
+        (Some((path, true)), Pat::Tuple(pat_tuple)) => {
+            if let Some(elem) = pat_tuple.elems.iter().nth(1) {
+                bind_fields(elem, &path, false, found_instances);
+            }
+        }
+        (Some((path, false)), _) => bind_fields(pat, &path, false, found_instances),
+        _ => {}
+    }
+}
+
+/// Resolves the field path of the elements yielded by an iterator over a field, e.g. `entries[]`
+/// for `self.entries.iter_mut()` or `&mut self.entries`. Also tells if the elements are
+/// enumerated, as adapters such as `rev()` or `skip(n)` are looked through.
+fn element_path(expr: &Expr, found_instances: &Instances) -> Option<(String, bool)> {
+    match expr {
+        Expr::MethodCall(call) => {
+            let method = call.method.to_string();
+            if method == "enumerate" {
+                element_path(&call.receiver, found_instances).map(|(path, _)| (path, true))
+            } else if ITER_ADAPTERS.contains(&method.as_str()) {
+                element_path(&call.receiver, found_instances)
+            } else if ITER_METHODS.contains(&method.as_str()) {
+                place_path(&call.receiver, found_instances).map(|path| (format!("{}[]", path), false))
+            } else {
+                None
+            }
+        }
+        Expr::Reference(reference) => {
+            place_path(&reference.expr, found_instances).map(|path| (format!("{}[]", path), false))
+        }
+        Expr::Paren(paren) => element_path(&paren.expr, found_instances),
+        _ => None,
+    }
+}
+
+/// Resolves the field a new binding refers to, e.g. `count` for `&mut self.count`,
+/// `child` for `self.child.as_mut()` and the instance itself for `&mut *self`.
+/// Values copied out of a field, such as `self.count`, are not aliases.
+fn alias_path(expr: &Expr, found_instances: &Instances) -> Option<String> {
+    match expr {
+        Expr::Reference(reference) => place_path(&reference.expr, found_instances),
+        Expr::Path(_) | Expr::MethodCall(_) => place_path(expr, found_instances),
+        Expr::Paren(paren) => alias_path(&paren.expr, found_instances),
+        // Raw pointers, e.g. `addr_of_mut!(self.count)` or `&mut self.count as *mut u32`.
+        Expr::RawAddr(raw) => place_path(&raw.expr, found_instances),
+        Expr::Cast(cast) => alias_path(&cast.expr, found_instances),
+        Expr::Macro(expr_macro) => addr_of_mut_place(&expr_macro.mac)
+            .and_then(|place| place_path(&place, found_instances)),
+        _ => None,
     }
 }
 
+/// Returns the place of an `addr_of_mut!(place)` invocation.
+fn addr_of_mut_place(mac: &Macro) -> Option<Expr> {
+    let is_addr_of_mut = mac.path.segments.last().is_some_and(|seg| seg.ident == "addr_of_mut");
+    if is_addr_of_mut { mac.parse_body::<Expr>().ok() } else { None }
+}
+
+/// Returns the place a pointer cast points to, e.g. `self.count` in `&self.count as *const u32`.
+/// Mutable borrows are left out, as they are already mutations on their own.
+fn pointee_place(expr: &Expr) -> Option<&Expr> {
+    match expr {
+        Expr::Reference(reference) if reference.mutability.is_none() => Some(&reference.expr),
+        Expr::RawAddr(raw) if matches!(raw.mutability, PointerMutability::Const(_)) => Some(&raw.expr),
+        Expr::Path(_) => Some(expr),
+        Expr::Cast(cast) => pointee_place(&cast.expr),
+        Expr::Paren(paren) => pointee_place(&paren.expr),
+        _ => None,
+    }
+}
+
+/// Tracks the positional fields of a tuple or tuple struct pattern.
+fn bind_elements(elems: &Punctuated<Pat, Comma>, path: &str, by_ref_only: bool, found_instances: &mut Instances) {
+    // Elements after a `..` rest pattern have unknown indices.
+    for (index, elem) in elems.iter().enumerate().take_while(|(_, elem)| !matches!(elem, Pat::Rest(_))) {
+        bind_fields(elem, &join_path(path, &index.to_string()), by_ref_only, found_instances);
+    }
+}
 
-/// Extracts all inner instance names from the function 
-/// body if matches the specified struct_name on Expr::CAll.
+/// Extracts all inner instance names from the function body, i.e. the `let` bindings
+/// whose syntactic type is the target struct: `let x: MyStruct = build();`, or a value
+/// built by `MyStruct { .. }`, `MyStruct::new()`, `Self::with_capacity(n)` and the like.
 fn extract_inner_instance(
     left: &Pat,
     right: &Expr,
-    found_instances: &mut HashSet<String>,
-    struct_name: &str,
+    found_instances: &mut Instances,
+    target: &Target,
 ) {
-    // TODO: Check later on if there are more 
-    // complicated cases for the struct initialization.
-    if let Expr::Call(ExprCall { func, .. }) = right {
-        if let Expr::Path(ExprPath { path, .. }) = &**func {
-            let segments = &path.segments;
-            // Ensure the first segment matches the struct_name.
-            if segments.len() > 0 && segments[0].ident == struct_name {
-                // Check if the next segment is an initialization method.
-                if segments.len() > 1 {
-                    let init_method = &segments[1].ident.to_string();
-                    if init_method == "default" || init_method == "new" {
-                        // Extract instance name from the left side of the initialization.
-                        if let Pat::Ident(PatIdent { ident, .. }) = left {
-                            found_instances.insert(ident.to_string());
-                            // println!("Instance found: {}", ident.to_string());
-                        }
-                    }
-                }
+    match left {
+        Pat::Type(PatType { pat, ty, .. }) => {
+            // The written type decides, whatever the initialization expression.
+            bind_instance(pat, ty, target, found_instances);
+        }
+        Pat::Ident(PatIdent { ident, subpat: None, .. }) if is_constructed(right, target) => {
+            // Extract instance name from the left side of the initialization.
+            found_instances.insert(ident.to_string(), String::new());
+        }
+        _ => {}
+    }
+}
+
+/// Checks if an expression builds a new value of the target struct. Values chosen by
+/// `if` or `match` are constructed if any of the branches constructs one.
+fn is_constructed(expr: &Expr, target: &Target) -> bool {
+    match expr {
+        Expr::Struct(ExprStruct { qself: None, path, .. }) => target.matches_path(path),
+        // Associated functions such as `MyStruct::new()` or `mm::MyStruct::<T>::from_raw(ptr)`.
+        Expr::Call(ExprCall { func, .. }) => match &**func {
+            Expr::Path(ExprPath { qself: None, path, .. }) if path.segments.len() > 1 => {
+                let type_path = Path {
+                    leading_colon: None,
+                    segments: path.segments.iter().take(path.segments.len() - 1).cloned().collect(),
+                };
+                target.matches_path(&type_path)
             }
+            _ => false,
+        },
+        // Fallible constructors, e.g. `MyStruct::try_new()?` or `MyStruct::try_new().unwrap()`.
+        Expr::Try(expr) => is_constructed(&expr.expr, target),
+        Expr::MethodCall(call) if call.method == "unwrap" || call.method == "expect" => {
+            is_constructed(&call.receiver, target)
         }
+        Expr::Paren(paren) => is_constructed(&paren.expr, target),
+        Expr::Block(ExprBlock { block, .. }) => block_value(block).is_some_and(|expr| is_constructed(expr, target)),
+        Expr::Unsafe(expr) => block_value(&expr.block).is_some_and(|expr| is_constructed(expr, target)),
+        Expr::If(ExprIf { then_branch, else_branch, .. }) => {
+            block_value(then_branch).is_some_and(|expr| is_constructed(expr, target))
+                || else_branch.as_ref().is_some_and(|(_, expr)| is_constructed(expr, target))
+        }
+        Expr::Match(ExprMatch { arms, .. }) => arms.iter().any(|arm| is_constructed(&arm.body, target)),
+        _ => false,
+    }
+}
+
+/// Returns the tail expression giving the value of a block.
+fn block_value(block: &Block) -> Option<&Expr> {
+    match block.stmts.last() {
+        Some(Stmt::Expr(expr, None)) => Some(expr),
+        _ => None,
     }
 }
 
@@ -153,13 +754,17 @@ impl Error {
 }
 
 fn check_whitelist(
-    field_ident_str: &String, 
+    field_ident_str: &str, 
     whitelist: &[String], 
     errors: &mut Vec<Error>, 
     message: &str, 
     mode: bool,
 ) {
-    let is_whitelisted = whitelist.contains(field_ident_str);
+    // The whitelist allows the listed paths and everything nested inside them, while the
+    // denylist also rejects writes to their parents, e.g. a whole overwrite touches every field.
+    let is_whitelisted = whitelist.iter().any(|listed| {
+        covers(listed, field_ident_str) || (mode && covers(field_ident_str, listed))
+    });
 
     if mode {
         if is_whitelisted {
@@ -185,34 +790,292 @@ where
     println!("{}: {}", label, item_string);
 }
 
+/// Checks if a binary operator is a compound assignment such as `+=` or `<<=`.
+fn is_compound_assign(op: &BinOp) -> bool {
+    matches!(op,
+        BinOp::AddAssign(_) | BinOp::SubAssign(_) | BinOp::MulAssign(_) | BinOp::DivAssign(_)
+        | BinOp::RemAssign(_) | BinOp::BitXorAssign(_) | BinOp::BitAndAssign(_)
+        | BinOp::BitOrAssign(_) | BinOp::ShlAssign(_) | BinOp::ShrAssign(_))
+}
+
+/// Checks if a function is one of `mem::swap`, `mem::replace`, `mem::take` or `ptr::write`.
+fn is_writing_function(func: &Expr) -> bool {
+    let Expr::Path(ExprPath { path, .. }) = func else {
+        return false;
+    };
+    let segments: Vec<String> = path.segments.iter().map(|seg| seg.ident.to_string()).collect();
+    match segments.as_slice() {
+        [.., module, function] => WRITING_FUNCTIONS.iter()
+            .any(|(m, f)| m == module && f == function),
+        _ => false,
+    }
+}
+
+/// Resolves a written place to its field path within a tracked instance, e.g. `count` for
+/// `self.count`, `inner.count` for `self.inner.count`, `0` for `self.0` and `table[]` for `self.table[i]`.
+/// Bindings destructured from a field resolve to that field, so `*count += 1` writes `count`.
+fn mutated_field(expr: &Expr, found_instances: &Instances) -> Option<String> {
+    place_path(expr, found_instances).filter(|path| !path.is_empty())
+}
+
+/// Resolves a place to its field path, the tracked instance itself resolves to an empty path.
+fn place_path(expr: &Expr, found_instances: &Instances) -> Option<String> {
+    match expr {
+        Expr::Path(ExprPath { path, .. }) => path.get_ident()
+            .and_then(|ident| found_instances.get(&ident.to_string()))
+            .cloned(),
+        Expr::Field(ExprField { base, member, .. }) => {
+            place_path(base, found_instances).map(|path| join_path(&path, &member_name(member)))
+        }
+        Expr::Index(index) => place_path(&index.expr, found_instances).map(|path| format!("{}[]", path)),
+        // Writing through a field such as `*self.count` writes the field itself.
+        Expr::Unary(ExprUnary { op: UnOp::Deref(_), expr, .. }) => place_path(expr, found_instances),
+        Expr::MethodCall(call) if is_access_call(call) => place_path(&call.receiver, found_instances),
+        Expr::Paren(paren) => place_path(&paren.expr, found_instances),
+        // Writing through a fresh borrow, e.g. `*(&raw mut self.count) = 0`.
+        Expr::Reference(reference) => place_path(&reference.expr, found_instances),
+        Expr::RawAddr(raw) => place_path(&raw.expr, found_instances),
+        _ => None,
+    }
+}
+
+/// Checks if a method call borrows its receiver, e.g. `self.child.as_mut()` or
+/// `self.state.lock().unwrap()`, but not `self.option.unwrap()`.
+fn is_access_call(call: &ExprMethodCall) -> bool {
+    let method = call.method.to_string();
+    if ACCESS_METHODS.contains(&method.as_str()) {
+        return true;
+    }
+    UNWRAP_METHODS.contains(&method.as_str())
+        && matches!(&*call.receiver, Expr::MethodCall(receiver) if is_access_call(receiver))
+}
+
+fn member_name(member: &Member) -> String {
+    match member {
+        Member::Named(ident) => ident.to_string(),
+        Member::Unnamed(index) => index.index.to_string(),
+    }
+}
+
+fn join_path(path: &str, member: &str) -> String {
+    if path.is_empty() {
+        member.to_string()
+    } else {
+        format!("{}.{}", path, member)
+    }
+}
+
+/// Checks if a listed field path covers a mutated one, i.e. the mutated path is the
+/// listed path or nested inside it: `inner` covers `inner.count` and `table` covers `table[]`.
+fn covers(listed: &str, field: &str) -> bool {
+    if listed == WILDCARD || listed == field {
+        return true;
+    }
+    field.strip_prefix(listed)
+        .is_some_and(|rest| rest.starts_with('.') || rest.starts_with('['))
+}
+
+/// Resolves `instance`, `*instance` or `(*instance)` to the tracked instance name.
+fn instance_name(expr: &Expr, found_instances: &Instances) -> Option<String> {
+    match expr {
+        Expr::Path(ExprPath { path, .. }) => path.get_ident()
+            .map(|ident| ident.to_string())
+            .filter(|name| found_instances.get(name).is_some_and(|path| path.is_empty())),
+        Expr::Unary(ExprUnary { op: UnOp::Deref(_), expr, .. }) => instance_name(expr, found_instances),
+        Expr::Paren(paren) => instance_name(&paren.expr, found_instances),
+        _ => None,
+    }
+}
+
+/// Resolves a place overwriting a whole tracked instance, i.e. `*self` as an assignee,
+/// or `self` and `&mut *self` passed to `mem::swap` and friends.
+fn overwritten_instance(expr: &Expr, found_instances: &Instances) -> Option<String> {
+    match expr {
+        Expr::Unary(ExprUnary { op: UnOp::Deref(_), expr, .. }) => instance_name(expr, found_instances),
+        Expr::Reference(reference) if reference.mutability.is_some() => {
+            overwritten_instance(&reference.expr, found_instances)
+        }
+        Expr::Path(_) => instance_name(expr, found_instances),
+        Expr::Paren(paren) => overwritten_instance(&paren.expr, found_instances),
+        _ => None,
+    }
+}
+
+fn check_place_for_mutation(
+    place: &Expr,
+    whitelist: &[String],
+    errors: &mut Vec<Error>,
+    found_instances: &Instances,
+    target: &Target,
+    mode: bool,
+) {
+    if let Some(field) = mutated_field(place, found_instances) {
+        check_whitelist(
+            &field,
+            whitelist,
+            errors,
+            &format!("Mutation to field `{}::{}`", target.name, field),
+            mode
+        );
+    }
+}
+
+/// Walks a written place for the mutations in its operands, e.g. the index in
+/// `self.table[{ self.count += 1; 0 }]`. The access calls a field is written through, e.g.
+/// `lock` in `self.state.lock().unwrap().count = 0`, are part of the write already checked.
+fn check_written_place(
+    place: &Expr,
+    whitelist: &[String],
+    errors: &mut Vec<Error>,
+    found_instances: &mut Instances,
+    target: &Target,
+    mode: bool,
+) {
+    if mutated_field(place, found_instances).is_none() {
+        check_expr_for_mutation(place, whitelist, errors, found_instances, target, mode);
+        return;
+    }
+    match place {
+        Expr::Field(ExprField { base, .. }) => {
+            check_written_place(base, whitelist, errors, found_instances, target, mode);
+        }
+        Expr::Index(index) => {
+            check_written_place(&index.expr, whitelist, errors, found_instances, target, mode);
+            check_expr_for_mutation(&index.index, whitelist, errors, found_instances, target, mode);
+        }
+        Expr::Unary(ExprUnary { op: UnOp::Deref(_), expr, .. }) => {
+            check_written_place(expr, whitelist, errors, found_instances, target, mode);
+        }
+        Expr::Paren(paren) => {
+            check_written_place(&paren.expr, whitelist, errors, found_instances, target, mode);
+        }
+        Expr::MethodCall(call) if is_access_call(call) => {
+            check_written_place(&call.receiver, whitelist, errors, found_instances, target, mode);
+            check_exprs_for_mutation(&call.args, whitelist, errors, found_instances, target, mode);
+        }
+        _ => check_expr_for_mutation(place, whitelist, errors, found_instances, target, mode),
+    }
+}
+
+fn check_overwrite(
+    instance: &str,
+    whitelist: &[String],
+    errors: &mut Vec<Error>,
+    target: &Target,
+    mode: bool,
+) {
+    check_whitelist(
+        WILDCARD,
+        whitelist,
+        errors,
+        &format!("Overwrite of the whole `{}` instance `{}`", target.name, instance),
+        mode
+    );
+}
+
+/// Checks a mutable raw pointer to a place, which lets the field be written without being
+/// seen, e.g. `addr_of_mut!(self.count)`, `&raw mut self.count` or `self as *mut Self`.
+fn check_pointer_to_place(
+    place: &Expr,
+    whitelist: &[String],
+    errors: &mut Vec<Error>,
+    found_instances: &Instances,
+    target: &Target,
+    mode: bool,
+) {
+    if let Some(field) = mutated_field(place, found_instances) {
+        check_whitelist(
+            &field,
+            whitelist,
+            errors,
+            &format!("Mutable raw pointer to field `{}::{}`", target.name, field),
+            mode
+        );
+    } else if let Some(instance) = overwritten_instance(place, found_instances) {
+        check_whitelist(
+            WILDCARD,
+            whitelist,
+            errors,
+            &format!("Mutable raw pointer to the whole `{}` instance `{}`", target.name, instance),
+            mode
+        );
+    }
+}
+
 // Recursive check all statements in the block.
 fn check_block_for_mutation(
     block: &Block,
     whitelist: &[String],
-    found_instances: &mut HashSet<String>,
-    struct_name: &str,
+    found_instances: &mut Instances,
+    target: &Target,
     errors: &mut Vec<Error>,
     mode: bool,
 ) {
+    // Bindings introduced in the block are only visible inside of it.
+    found_instances.push();
     for stmt in &block.stmts {
         match stmt {
             Stmt::Expr(expr, _) => {
                 // print_ast(expr, "Found Expression");
                 // Explore Netsted Expression for struct field mutation.
-                check_expr_for_mutation(expr, whitelist, errors, found_instances, struct_name, mode);
+                check_expr_for_mutation(expr, whitelist, errors, found_instances, target, mode);
+            }
+            Stmt::Macro(stmt_macro) => {
+                check_macro_for_mutation(&stmt_macro.mac, whitelist, errors, found_instances, target, mode);
             }
             Stmt::Local(Local { pat, init, .. }) => {
+                // print_ast(&init.expr, "Found Initialization Expression");
+                // Check the initialization expression for instance names and mutation.
+                if let Some(init) = init {
+                    check_expr_for_mutation(&init.expr, whitelist, errors, found_instances, target, mode);
+                    // The `else` branch of `let ... else` runs before the pattern is bound.
+                    if let Some((_, diverge)) = &init.diverge {
+                        check_expr_for_mutation(diverge, whitelist, errors, found_instances, target, mode);
+                    }
+                }
+                // Follow references borrowed from an instance, e.g. `let r = &mut self.field;`.
+                bind_pattern(pat, init.as_ref().map(|init| &*init.expr), found_instances);
+                // Extract instance name if initialization expression is a struct creation.
                 if let Some(init) = init {
-                    // print_ast(&init.expr, "Found Initialization Expression");
-                    // Extract instance name if initialization expression is a struct creation.
-                    extract_inner_instance(pat, &init.expr, found_instances, struct_name);
-                    // Check the initialization expression for instance names and mutation.
-                    check_expr_for_mutation(&init.expr, whitelist, errors, found_instances, struct_name, mode);
+                    extract_inner_instance(pat, &init.expr, found_instances, target);
                 }
             }
-            _ => {}
+            // Nested items are not part of the function body.
+            Stmt::Item(_) => {}
         }
     }
+    found_instances.pop();
+}
+
+/// Checks a macro invocation: `addr_of_mut!(self.field)` takes a mutable raw pointer,
+/// and the arguments of expression-like macros such as `assert!` or `vec!` are walked.
+fn check_macro_for_mutation(
+    mac: &Macro,
+    whitelist: &[String],
+    errors: &mut Vec<Error>,
+    found_instances: &mut Instances,
+    target: &Target,
+    mode: bool,
+) {
+    if let Some(place) = addr_of_mut_place(mac) {
+        check_pointer_to_place(&place, whitelist, errors, found_instances, target, mode);
+    }
+    for arg in parse_macro_args(mac) {
+        check_expr_for_mutation(&arg, whitelist, errors, found_instances, target, mode);
+    }
+}
+
+fn check_exprs_for_mutation<'a>(
+    exprs: impl IntoIterator<Item = &'a Expr>,
+    whitelist: &[String],
+    errors: &mut Vec<Error>,
+    found_instances: &mut Instances,
+    target: &Target,
+    mode: bool,
+) {
+    for expr in exprs {
+        check_expr_for_mutation(expr, whitelist, errors, found_instances, target, mode);
+    }
 }
 
 
@@ -220,104 +1083,236 @@ fn check_expr_for_mutation(
     expr: &Expr,
     whitelist: &[String],
     errors: &mut Vec<Error>,
-    found_instances: &mut HashSet<String>,
-    struct_name: &str,
+    found_instances: &mut Instances,
+    target: &Target,
     mode: bool,
 ) {
     match expr {
+        Expr::Binary(binary_expr) if is_compound_assign(&binary_expr.op) => {
+            // Handle compound assignments such as `+=` or `<<=`.
+            check_place_for_mutation(&binary_expr.left, whitelist, errors, found_instances, target, mode);
+            check_written_place(&binary_expr.left, whitelist, errors, found_instances, target, mode);
+            check_expr_for_mutation(&binary_expr.right, whitelist, errors, found_instances, target, mode);
+        }
+
         Expr::Binary(binary_expr) => {
-            // Handle various binary operations, including compound assignments.
-            if let Expr::Field(ExprField { base, member, .. }) = &*binary_expr.left {
-                if let Member::Named(field_ident) = member {
-                    // Check if the base is one of the found instances.
-                    if let Expr::Path(ExprPath { path, .. }) = &**base {
-                        if let Some(instance) = path.get_ident() {
-                            let instance_name = instance.to_string();
-                            if found_instances.contains(&instance_name) {
-                                let field_ident_str = field_ident.to_string();
-                                check_whitelist(
-                                    &field_ident_str,
-                                    whitelist,
-                                    errors,
-                                    &format!("Mutation to field `{}::{}`", struct_name, field_ident_str),
-                                    mode
-                                );
-                            }
-                        }
-                    }
-                }
-            }
+            // Comparisons and arithmetic only read the fields, but the operands may mutate.
+            check_expr_for_mutation(&binary_expr.left, whitelist, errors, found_instances, target, mode);
+            check_expr_for_mutation(&binary_expr.right, whitelist, errors, found_instances, target, mode);
         }
 
         Expr::Assign(assign_expr) => {
             // Handle simple assignments (fails for everything => this is a mutation).
-            if let Expr::Field(ExprField { base, member, .. }) = &*assign_expr.left {
-                if let Member::Named(field_ident) = member {
-                    if let Expr::Path(ExprPath { path, .. }) = &**base {
-                        if let Some(instance) = path.get_ident() {
-                            let instance_name = instance.to_string();
-                            if found_instances.contains(&instance_name) {
-                                let field_ident_str = field_ident.to_string();
-                                check_whitelist(
-                                    &field_ident_str,
-                                    whitelist,
-                                    errors,
-                                    &format!("Mutation to field `{}::{}`", struct_name, field_ident_str),
-                                    mode
-                                );
-                            }
-                        }
+            if let Some(instance) = overwritten_instance(&assign_expr.left, found_instances) {
+                // `*self = MyStruct { .. }` overwrites every field at once.
+                check_overwrite(&instance, whitelist, errors, target, mode);
+            } else {
+                check_place_for_mutation(&assign_expr.left, whitelist, errors, found_instances, target, mode);
+            }
+            // The place itself may hold mutations, e.g. in an index: `self.table[{ self.count += 1; 0 }]`.
+            check_written_place(&assign_expr.left, whitelist, errors, found_instances, target, mode);
+            check_expr_for_mutation(&assign_expr.right, whitelist, errors, found_instances, target, mode);
+        }
+
+        Expr::Reference(reference) => {
+            // Handle mutable borrows such as `&mut self.field`.
+            if reference.mutability.is_some() {
+                check_place_for_mutation(&reference.expr, whitelist, errors, found_instances, target, mode);
+                check_written_place(&reference.expr, whitelist, errors, found_instances, target, mode);
+            } else {
+                check_expr_for_mutation(&reference.expr, whitelist, errors, found_instances, target, mode);
+            }
+        }
+
+        Expr::MethodCall(call) => {
+            // Handle `&mut self` methods called on a field, e.g. `self.frames.push(frame)`,
+            // and interior mutability through `&self`, e.g. `self.count.set(0)` or `self.state.lock()`.
+            if target.is_mutating_method(call) {
+                // A lock taken for a listed nested field is checked where that field is written.
+                let is_nested_access = !mode
+                    && ACCESS_METHODS.contains(&call.method.to_string().as_str())
+                    && mutated_field(&call.receiver, found_instances)
+                        .is_some_and(|field| whitelist.iter().any(|listed| covers(&field, listed)));
+                if !is_nested_access {
+                    check_place_for_mutation(&call.receiver, whitelist, errors, found_instances, target, mode);
+                }
+                check_written_place(&call.receiver, whitelist, errors, found_instances, target, mode);
+            } else {
+                check_expr_for_mutation(&call.receiver, whitelist, errors, found_instances, target, mode);
+            }
+            check_exprs_for_mutation(&call.args, whitelist, errors, found_instances, target, mode);
+        }
+
+        Expr::Call(call) => {
+            // Handle `mem::swap`, `mem::replace`, `mem::take` and `ptr::write` on the whole instance,
+            // writes to a field are already borrowed as `&mut self.field` in the arguments.
+            if is_writing_function(&call.func) {
+                for arg in &call.args {
+                    if let Some(instance) = overwritten_instance(arg, found_instances) {
+                        check_overwrite(&instance, whitelist, errors, target, mode);
                     }
                 }
             }
+            check_expr_for_mutation(&call.func, whitelist, errors, found_instances, target, mode);
+            check_exprs_for_mutation(&call.args, whitelist, errors, found_instances, target, mode);
+        }
+
+        Expr::Paren(paren) => {
+            check_expr_for_mutation(&paren.expr, whitelist, errors, found_instances, target, mode);
+        }
+
+        Expr::RawAddr(raw) => {
+            // Handle `&raw mut self.field`, while `&raw const` only reads.
+            if matches!(raw.mutability, PointerMutability::Mut(_)) {
+                check_pointer_to_place(&raw.expr, whitelist, errors, found_instances, target, mode);
+            }
+            check_expr_for_mutation(&raw.expr, whitelist, errors, found_instances, target, mode);
+        }
+
+        Expr::Cast(cast) => {
+            // Handle casts to mutable pointers, e.g. `&self.field as *const _ as *mut _`.
+            if let (Type::Ptr(TypePtr { mutability: Some(_), .. }), Some(place)) = (&*cast.ty, pointee_place(&cast.expr)) {
+                check_pointer_to_place(place, whitelist, errors, found_instances, target, mode);
+            }
+            check_expr_for_mutation(&cast.expr, whitelist, errors, found_instances, target, mode);
+        }
+
+        Expr::Macro(expr_macro) => {
+            // Handle `addr_of_mut!(self.field)` and the arguments of macros such as `assert!`.
+            check_macro_for_mutation(&expr_macro.mac, whitelist, errors, found_instances, target, mode);
+        }
+
+        Expr::Unsafe(ExprUnsafe { block, .. }) => {
+            // Handle `unsafe { ... }` blocks, where raw pointer writes usually happen.
+            check_block_for_mutation(block, whitelist, found_instances, target, errors, mode);
         }
 
         Expr::Block(ExprBlock { block, .. }) => {
             // Handle a block of code: `{ ... }`.
-            check_block_for_mutation(&block, whitelist, found_instances, struct_name, errors, mode);
+            check_block_for_mutation(block, whitelist, found_instances, target, errors, mode);
         }
 
-        Expr::If(ExprIf { then_branch, else_branch, .. }) => {
-            // Process the `then` block.
-            check_block_for_mutation(&then_branch, whitelist, found_instances, struct_name, errors, mode);
+        Expr::Async(ExprAsync { block, .. }) | Expr::Const(ExprConst { block, .. }) | Expr::TryBlock(ExprTryBlock { block, .. }) => {
+            // Handle `async`, `const` and `try` blocks, which are not run in place but still mutate.
+            check_block_for_mutation(block, whitelist, found_instances, target, errors, mode);
+        }
+
+        Expr::Loop(ExprLoop { body, .. }) => {
+            // Handle `loop { ... }`.
+            check_block_for_mutation(body, whitelist, found_instances, target, errors, mode);
+        }
+
+        Expr::If(ExprIf { cond, then_branch, else_branch, .. }) => {
+            // Process the condition and the `then` block, where `if let` bindings are visible.
+            found_instances.push();
+            check_expr_for_mutation(cond, whitelist, errors, found_instances, target, mode);
+            check_block_for_mutation(then_branch, whitelist, found_instances, target, errors, mode);
+            found_instances.pop();
             // Process the `else` branch if present.
             if let Some((_, else_expr)) = else_branch {
                 match &**else_expr {
                     Expr::Block(ExprBlock { block, .. }) => {
                         // Process the block inside `else_expr`
-                        check_block_for_mutation(&block, whitelist, found_instances, struct_name, errors, mode);
+                        check_block_for_mutation(block, whitelist, found_instances, target, errors, mode);
                     },
-                    // Handle other types of `else_expr` if necessary
-                    _ => check_expr_for_mutation(expr, whitelist, errors, found_instances, struct_name, mode),
+                    // Handle `else if` chains by processing the nested `if` expression.
+                    _ => check_expr_for_mutation(else_expr, whitelist, errors, found_instances, target, mode),
                 }
             }
         }
 
-        Expr::While(ExprWhile { body, .. }) => {
+        Expr::Match(ExprMatch { expr, arms, .. }) => {
+            // Handle `match` arms, whose bindings may refer to the matched field.
+            check_expr_for_mutation(expr, whitelist, errors, found_instances, target, mode);
+            for arm in arms {
+                found_instances.push();
+                bind_pattern(&arm.pat, Some(expr), found_instances);
+                if let Some((_, guard)) = &arm.guard {
+                    check_expr_for_mutation(guard, whitelist, errors, found_instances, target, mode);
+                }
+                check_expr_for_mutation(&arm.body, whitelist, errors, found_instances, target, mode);
+                found_instances.pop();
+            }
+        }
+
+        Expr::Let(expr_let) => {
+            // Handle `if let` and `while let`, e.g. `if let Some(child) = self.child.as_mut()`.
+            check_expr_for_mutation(&expr_let.expr, whitelist, errors, found_instances, target, mode);
+            bind_pattern(&expr_let.pat, Some(&expr_let.expr), found_instances);
+        }
+
+        Expr::While(ExprWhile { cond, body, .. }) => {
             // Handle the expression inside the while loop (always block).
+            found_instances.push();
+            check_expr_for_mutation(cond, whitelist, errors, found_instances, target, mode);
             check_block_for_mutation(
-                &body, 
+                body, 
                 whitelist, 
                 found_instances, 
-                struct_name,
+                target,
                 errors,
                 mode);
+            found_instances.pop();
         }
 
-        Expr::ForLoop(ExprForLoop { body, .. }) => {
+        Expr::ForLoop(ExprForLoop { pat, expr, body, .. }) => {
             // Handle the expression inside the for loop (always block).
-            check_block_for_mutation(&body, whitelist, found_instances, struct_name, errors, mode);
+            check_expr_for_mutation(expr, whitelist, errors, found_instances, target, mode);
+            found_instances.push();
+            bind_loop_pattern(pat, expr, found_instances);
+            check_block_for_mutation(body, whitelist, found_instances, target, errors, mode);
+            found_instances.pop();
         }
 
-        Expr::Closure(ExprClosure { body, .. }) => {
-            // Handle closures (either block or expression).
+        Expr::Closure(ExprClosure { inputs, body, .. }) => {
+            // Handle closures (either block or expression), the parameters shadow outer bindings.
+            found_instances.push();
+            for input in inputs {
+                bind_pattern(input, None, found_instances);
+            }
             if let Expr::Block(ExprBlock { block, .. }) = &**body {
-                check_block_for_mutation(block, whitelist, found_instances, struct_name, errors, mode);
+                check_block_for_mutation(block, whitelist, found_instances, target, errors, mode);
             } else {
-                check_expr_for_mutation(body, whitelist, errors, found_instances, struct_name, mode);
+                check_expr_for_mutation(body, whitelist, errors, found_instances, target, mode);
             }
+            found_instances.pop();
+        }
+
+        // Handle the operands of the remaining expressions, e.g. `return` values, call arguments or tuples.
+        Expr::Array(array) => check_exprs_for_mutation(&array.elems, whitelist, errors, found_instances, target, mode),
+        Expr::Tuple(tuple) => check_exprs_for_mutation(&tuple.elems, whitelist, errors, found_instances, target, mode),
+        Expr::Await(expr_await) => check_expr_for_mutation(&expr_await.base, whitelist, errors, found_instances, target, mode),
+        Expr::Field(field) => check_expr_for_mutation(&field.base, whitelist, errors, found_instances, target, mode),
+        Expr::Group(group) => check_expr_for_mutation(&group.expr, whitelist, errors, found_instances, target, mode),
+        Expr::Try(expr_try) => check_expr_for_mutation(&expr_try.expr, whitelist, errors, found_instances, target, mode),
+        Expr::Unary(unary) => check_expr_for_mutation(&unary.expr, whitelist, errors, found_instances, target, mode),
+        Expr::Index(index) => {
+            check_expr_for_mutation(&index.expr, whitelist, errors, found_instances, target, mode);
+            check_expr_for_mutation(&index.index, whitelist, errors, found_instances, target, mode);
+        }
+        Expr::Range(range) => {
+            let bounds = range.start.iter().chain(&range.end).map(|bound| &**bound);
+            check_exprs_for_mutation(bounds, whitelist, errors, found_instances, target, mode);
+        }
+        Expr::Repeat(repeat) => {
+            check_expr_for_mutation(&repeat.expr, whitelist, errors, found_instances, target, mode);
+            check_expr_for_mutation(&repeat.len, whitelist, errors, found_instances, target, mode);
+        }
+        Expr::Struct(ExprStruct { fields, rest, .. }) => {
+            check_exprs_for_mutation(fields.iter().map(|field| &field.expr), whitelist, errors, found_instances, target, mode);
+            check_exprs_for_mutation(rest.as_deref(), whitelist, errors, found_instances, target, mode);
+        }
+        Expr::Return(expr_return) => {
+            check_exprs_for_mutation(expr_return.expr.as_deref(), whitelist, errors, found_instances, target, mode);
+        }
+        Expr::Break(expr_break) => {
+            check_exprs_for_mutation(expr_break.expr.as_deref(), whitelist, errors, found_instances, target, mode);
+        }
+        Expr::Yield(expr_yield) => {
+            check_exprs_for_mutation(expr_yield.expr.as_deref(), whitelist, errors, found_instances, target, mode);
         }
 
+        // Literals, paths and `continue` do not mutate anything.
         _ => {}
     }
 }
@@ -0,0 +1,71 @@
+use syn::{Ident, ItemStruct};
+use proc_macro2::TokenStream as ProcTokenStream;
+use quote::{format_ident, quote, quote_spanned};
+use crate::parser::mutation_policies::{MutationPolicies, PolicyRef};
+
+/// Names the hidden module holding the policy macro of a struct.
+fn policy_module_name(struct_name: &Ident) -> Ident {
+    format_ident!("__mutation_policies_{}", struct_name)
+}
+
+/// Defines the named mutation policies of a struct.
+///
+/// The struct is emitted unchanged, followed by a hidden `macro_rules!` which maps each policy
+/// to its field list. `#[mutates(MyStruct::alloc)]` expands into a call to that macro, which
+/// puts `#[mutates(MyStruct: (...))]` back on the function, so the field list is written once.
+/// The macro is named after the struct and re-exported next to it, so importing the struct also
+/// imports its policies, as macros and types live in separate namespaces.
+pub fn assert_policies_impl(policies: &MutationPolicies, input: ItemStruct) -> ProcTokenStream {
+    let struct_name = &input.ident;
+    let module_name = policy_module_name(struct_name);
+
+    let arms = policies.policies.iter().map(|policy| {
+        let name = &policy.name;
+        let values = &policy.values;
+        quote! {
+            ($macro:ident, #name, $error:tt, $($item:tt)*) => {
+                #[$macro(#struct_name: (#(#values),*))]
+                $($item)*
+            };
+        }
+    });
+
+    quote! {
+        #input
+
+        #[doc(hidden)]
+        #[allow(non_snake_case)]
+        mod #module_name {
+            #[allow(unused_macros)]
+            macro_rules! #struct_name {
+                #(#arms)*
+                // The error is written by the caller, so that it points at the policy.
+                ($macro:ident, $policy:ident, { $($error:tt)* }, $($item:tt)*) => {
+                    $($error)*
+                    $($item)*
+                };
+            }
+
+            #[allow(unused_imports)]
+            pub(crate) use #struct_name;
+        }
+
+        #[allow(unused_imports)]
+        pub(crate) use #module_name::#struct_name;
+    }
+}
+
+/// Expands `#[macro_name(MyStruct::policy)]` into a call to the policy macro of the struct,
+/// which applies `#[macro_name(MyStruct: (...))]` to the item.
+pub fn expand_policy_ref(policy_ref: &PolicyRef, macro_name: &str, item: ProcTokenStream) -> ProcTokenStream {
+    let macro_path = &policy_ref.struct_path;
+    let struct_name = &macro_path.segments.last().unwrap().ident;
+    let policy = &policy_ref.policy;
+    let message = format!("Unknown mutation policy `{}` of `{}`", policy, struct_name);
+    let error = quote_spanned! { policy.span()=> compile_error!(#message); };
+
+    let macro_name = Ident::new(macro_name, proc_macro2::Span::call_site());
+    quote! {
+        #macro_path! { #macro_name, #policy, { #error }, #item }
+    }
+}
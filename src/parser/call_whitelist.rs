@@ -0,0 +1,127 @@
+use std::fmt;
+use syn::{
+    parse::{Parse, ParseStream},
+    Error, Ident, LitInt, LitStr, Result, Token,
+};
+
+pub struct WhitelistArgs {
+    pub only: bool,
+    pub on_all_paths: bool,
+    pub values: Vec<WhitelistEntry>,
+}
+
+/// A listed function with an optional constraint on the number of its call sites.
+pub struct WhitelistEntry {
+    pub name: String,
+    pub count: Option<Count>,
+}
+
+impl From<String> for WhitelistEntry {
+    fn from(name: String) -> Self {
+        WhitelistEntry { name, count: None }
+    }
+}
+
+/// A call cardinality constraint such as `== 1` or `<= 2`.
+#[derive(Debug, Clone, Copy)]
+pub struct Count {
+    pub op: Comparison,
+    pub value: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ge,
+    Le,
+    Gt,
+    Lt,
+}
+
+impl Count {
+    pub fn allows(&self, found: usize) -> bool {
+        match self.op {
+            Comparison::Eq => found == self.value,
+            Comparison::Ge => found >= self.value,
+            Comparison::Le => found <= self.value,
+            Comparison::Gt => found > self.value,
+            Comparison::Lt => found < self.value,
+        }
+    }
+}
+
+impl fmt::Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self.op {
+            Comparison::Eq => "==",
+            Comparison::Ge => ">=",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Lt => "<",
+        };
+        write!(f, "{} {}", op, self.value)
+    }
+}
+
+impl Parse for Count {
+    fn parse(input: ParseStream) -> Result<Self> {
+        // Two-character operators are checked first, so that `>=` is not read as `>`.
+        let op = if input.peek(Token![==]) {
+            input.parse::<Token![==]>()?;
+            Comparison::Eq
+        } else if input.peek(Token![>=]) {
+            input.parse::<Token![>=]>()?;
+            Comparison::Ge
+        } else if input.peek(Token![<=]) {
+            input.parse::<Token![<=]>()?;
+            Comparison::Le
+        } else if input.peek(Token![>]) {
+            input.parse::<Token![>]>()?;
+            Comparison::Gt
+        } else if input.peek(Token![<]) {
+            input.parse::<Token![<]>()?;
+            Comparison::Lt
+        } else {
+            return Err(input.error("Expected one of `==`, `>=`, `<=`, `>` or `<`"));
+        };
+
+        let value: LitInt = input.parse()?;
+        Ok(Count { op, value: value.base10_parse()? })
+    }
+}
+
+impl Parse for WhitelistArgs {
+    // #[calls(only, on_all_paths, "func1", "func2" == 1, "func3" >= 2, ...)]
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut only = false;
+        let mut on_all_paths = false;
+        let mut values = Vec::new();
+
+        while !input.is_empty() {
+            if input.peek(Ident) {
+                // Parse the mode keywords preceding the function names.
+                let option: Ident = input.parse()?;
+                match option.to_string().as_str() {
+                    "only" => only = true,
+                    "on_all_paths" => on_all_paths = true,
+                    _ => return Err(Error::new(option.span(), format!("Unknown option `{}`", option))),
+                }
+            } else {
+                let function_name: LitStr = input.parse()?;
+                // Parse the optional cardinality constraint.
+                let count = if input.is_empty() || input.peek(Token![,]) {
+                    None
+                } else {
+                    Some(input.parse::<Count>()?)
+                };
+                values.push(WhitelistEntry { name: function_name.value(), count });
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(WhitelistArgs { only, on_all_paths, values })
+    }
+}
@@ -1,38 +1,99 @@
 use syn::{
     self, parenthesized, 
     parse::{Parse, ParseStream, ParseBuffer}, 
-    Ident, LitStr, Result, Token};
+    Error, Ident, LitStr, Result, Token};
 
 pub struct WhitelistArgs {
+    pub structs: Vec<StructWhitelist>,
+    /// Extra methods mutating a field through a shared reference.
+    pub interior_methods: Vec<String>,
+}
+
+/// The whitelisted fields of one struct.
+pub struct StructWhitelist {
     pub struct_name: String,
     pub values: Vec<String>,
 }
 
 impl Parse for WhitelistArgs {
     fn parse (input: ParseStream) -> Result<Self> {
-        // #[mutates(struct_name: (field1, field2, ...))]
+        // #[mutates(struct_name: (field1, field2, ...), other_struct: (...), interior_methods = (...))]
+        let mut structs: Vec<StructWhitelist> = Vec::new();
+        let mut interior_methods: Vec<String> = Vec::new();
+        while !input.is_empty() {
+            let struct_ident: Ident = input.fork().parse()?;
+            if input.peek2(Token![=]) {
+                // Parse the options, which are assigned instead of listing fields.
+                input.parse::<Ident>()?;
+                input.parse::<Token![=]>()?;
+                match struct_ident.to_string().as_str() {
+                    "interior_methods" => interior_methods.extend(parse_names(input)?),
+                    _ => return Err(Error::new(struct_ident.span(), format!("Unknown option `{}`", struct_ident))),
+                }
+                if input.peek(Token![,]) {
+                    input.parse::<Token![,]>()?;
+                    continue;
+                }
+                break;
+            }
+            let struct_whitelist: StructWhitelist = input.parse()?;
+            if structs.iter().any(|s| s.struct_name == struct_whitelist.struct_name) {
+                return Err(Error::new(struct_ident.span(), format!("Struct `{}` is listed twice", struct_ident)));
+            }
+            structs.push(struct_whitelist);
+            // Check for more structs.
+            if input.peek(Token![,]) {
+                input.parse::<Token![,]>()?;
+            } else {
+                break;
+            }
+        }
+
+        if !input.is_empty() {
+            return Err(input.error("Expected `,` between the structs"));
+        }
+        if structs.is_empty() {
+            return Err(input.error("Expected `StructName: (\"field\", ...)`"));
+        }
+
+        Ok(WhitelistArgs { structs, interior_methods })
+    }
+}
+
+impl Parse for StructWhitelist {
+    fn parse (input: ParseStream) -> Result<Self> {
+        // struct_name: (field1, field2, ...)
         let struct_name: Ident = input.parse()?;
         let struct_name = struct_name.to_string();
         // Expect a column before func whitelist.
         input.parse::<Token![:]>()?;
         // Parse the field_names.
-        let content: ParseBuffer;
-        parenthesized!(content in input);
-        let mut values: Vec<String> = Vec::new();
-        while !content.is_empty() {
-            let value: LitStr = content.parse()?;
-            values.push(value.value());
-            // Check for more values.
-            if content.peek(Token![,]) {
-                content.parse::<Token![,]>()?;
-            } else {
-                break;
-            }
-        } 
+        let values = parse_names(input)?;
 
-        Ok(WhitelistArgs {
+        Ok(StructWhitelist {
             struct_name,
             values
         })
     }
 }
+
+/// Parses a parenthesized list of names, e.g. `("field1", "field2")`.
+pub fn parse_names(input: ParseStream) -> Result<Vec<String>> {
+    let content: ParseBuffer;
+    parenthesized!(content in input);
+    let mut values: Vec<String> = Vec::new();
+    while !content.is_empty() {
+        let value: LitStr = content.parse()?;
+        values.push(value.value());
+        // Check for more values.
+        if content.peek(Token![,]) {
+            content.parse::<Token![,]>()?;
+        } else {
+            break;
+        }
+    }
+    if !content.is_empty() {
+        return Err(content.error("Expected `,` between the names"));
+    }
+    Ok(values)
+}
@@ -0,0 +1,39 @@
+use syn::{
+    punctuated::Punctuated,
+    Expr, Macro, Token,
+};
+use quote::quote;
+
+/// Macros whose bodies are not a list of expressions, e.g. `matches!(x, Some(_))`.
+const NON_EXPR_MACROS: &[&str] = &[
+    "matches", "stringify", "concat_idents", "cfg", "env", "option_env",
+    "include", "include_str", "include_bytes", "module_path", "file",
+    "line", "column", "asm", "global_asm", "naked_asm",
+];
+
+/// Parses the body of a macro invocation as a comma separated list of expressions.
+///
+/// This covers the well-known std/core macros (`println!`, `format!`, `write!`, `assert!`,
+/// `vec![x; n]`, ...) and is a best-effort for user macros. If the body cannot be parsed
+/// as expressions, an empty list is returned and the macro is treated as opaque.
+pub fn parse_macro_args(mac: &Macro) -> Vec<Expr> {
+    let is_expr_macro = mac.path.segments.last()
+        .map(|seg| !NON_EXPR_MACROS.contains(&seg.ident.to_string().as_str()))
+        .unwrap_or(false);
+
+    if !is_expr_macro {
+        return Vec::new();
+    }
+
+    if let Ok(args) = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+        return args.into_iter().collect();
+    }
+
+    // Handle the `vec![value; count]` repeat form.
+    let tokens = &mac.tokens;
+    if let Ok(Expr::Repeat(repeat)) = syn::parse2::<Expr>(quote! { [#tokens] }) {
+        return vec![*repeat.expr, *repeat.len];
+    }
+
+    Vec::new()
+}
@@ -1,2 +1,6 @@
 pub mod field_whitelist;
+pub mod call_whitelist;
 pub mod whitelist;
+pub mod macro_args;
+pub mod skip;
+pub mod mutation_policies;
@@ -0,0 +1,70 @@
+use syn::{
+    parse::{Parse, ParseStream},
+    Error, Ident, Path, Result, Token,
+};
+use super::field_whitelist::parse_names;
+
+/// The named field lists of a struct.
+pub struct MutationPolicies {
+    pub policies: Vec<MutationPolicy>,
+}
+
+/// One named list of fields, e.g. `alloc = ("free_list", "count")`.
+pub struct MutationPolicy {
+    pub name: Ident,
+    pub values: Vec<String>,
+}
+
+impl Parse for MutationPolicies {
+    // #[mutation_policies(alloc = ("free_list", "count"), reset = ("*"))]
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut policies: Vec<MutationPolicy> = Vec::new();
+        while !input.is_empty() {
+            let name: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            let values = parse_names(input)?;
+            if policies.iter().any(|policy| policy.name == name) {
+                return Err(Error::new(name.span(), format!("Policy `{}` is defined twice", name)));
+            }
+            policies.push(MutationPolicy { name, values });
+            // Check for more policies.
+            if input.peek(Token![,]) {
+                input.parse::<Token![,]>()?;
+            } else {
+                break;
+            }
+        }
+
+        if !input.is_empty() {
+            return Err(input.error("Expected `,` between the policies"));
+        }
+        if policies.is_empty() {
+            return Err(input.error("Expected `policy = (\"field\", ...)`"));
+        }
+
+        Ok(MutationPolicies { policies })
+    }
+}
+
+/// A reference to a policy of a struct, e.g. `MyStruct::alloc` or `frames::Frame::alloc`.
+pub struct PolicyRef {
+    /// The path of the struct, which also names its policy macro.
+    pub struct_path: Path,
+    pub policy: Ident,
+}
+
+impl Parse for PolicyRef {
+    // #[mutates(MyStruct::alloc)]
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut struct_path = Path::parse_mod_style(input)?;
+        if struct_path.segments.len() < 2 || !input.is_empty() {
+            return Err(input.error("Expected `StructName::policy`"));
+        }
+        let policy = struct_path.segments.pop().unwrap().into_value().ident;
+        // Drop the trailing `::` left behind by the policy.
+        let last = struct_path.segments.pop().unwrap().into_value();
+        struct_path.segments.push(last);
+
+        Ok(PolicyRef { struct_path, policy })
+    }
+}
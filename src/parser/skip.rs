@@ -0,0 +1,18 @@
+use syn::{
+    parse::{Parse, ParseStream},
+    Error, Ident, Result,
+};
+
+/// The `skip` argument, which opts a function out of the policy of its enclosing item.
+pub struct Skip;
+
+impl Parse for Skip {
+    // #[calls(skip)] or #[mutates(skip)]
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ident: Ident = input.parse()?;
+        if ident != "skip" || !input.is_empty() {
+            return Err(Error::new(ident.span(), "Expected `skip`"));
+        }
+        Ok(Skip)
+    }
+}
@@ -1,5 +1,6 @@
 #![no_std]
 #![deny(unsafe_code)]
+#![allow(clippy::let_unit_value)]
 
 #[macro_use]
 extern crate proc_assertions;
@@ -61,6 +62,31 @@ mod simple_tests {
     //         disallowed_function();
     //     }
     // }
+
+    #[test]
+    fn test_only_function_calls() {
+        #[allow(dead_code)]
+        #[calls(only, "allowed_function", "target_function")]
+        pub fn my_function(instance: &MyStruct) {
+            allowed_function();
+            instance.target_function();
+            // ``` fails if uncommented, as neither call is listed
+            // allowed_function_not_checked();
+            // instance.target_function2();
+        }
+
+        my_function(&MyStruct);
+    }
+
+    // ``` fails, every call outside of the list is reported
+    // fn test_only_disallowed_function_calls() {
+    //     #[allow(dead_code)]
+    //     #[calls(only, "allowed_function")]
+    //     pub fn my_function() {
+    //         allowed_function();
+    //         disallowed_function();
+    //     }
+    // }
 }
 
 mod nested_tests {
@@ -91,3 +117,696 @@ mod nested_tests {
         }
     }
 }
+
+mod path_tests {
+    pub mod mm {
+        pub fn alloc() {}
+    }
+
+    pub mod heap {
+        pub fn alloc() {}
+    }
+
+    pub struct Frame;
+
+    impl Frame {
+        pub fn new() -> Self { Frame }
+    }
+
+    impl Clone for Frame {
+        fn clone(&self) -> Self { Frame }
+    }
+
+    #[test]
+    fn test_qualified_calls() {
+        // Qualified entries match the call by its path suffix.
+        #[allow(dead_code)]
+        #[calls("mm::alloc", "crate::path_tests::heap::alloc")]
+        pub fn my_function() {
+            crate::path_tests::mm::alloc();
+            crate::path_tests::heap::alloc();
+        }
+
+        my_function();
+    }
+
+    #[test]
+    fn test_unqualified_calls() {
+        // Unqualified entries match any function with that name.
+        #[allow(dead_code)]
+        #[calls("alloc")]
+        pub fn my_function() {
+            heap::alloc();
+        }
+
+        my_function();
+    }
+
+    #[test]
+    fn test_only_qualified_calls() {
+        #[allow(dead_code)]
+        #[calls(only, "mm::alloc", "Frame::new", "<Frame as Clone>::clone")]
+        pub fn my_function() {
+            mm::alloc();
+            let frame = Frame::new();
+            let _ = <Frame as Clone>::clone(&frame);
+            // ``` fails if uncommented, `heap::alloc` is not whitelisted
+            // heap::alloc();
+        }
+
+        my_function();
+    }
+
+    // ``` fails, `heap::alloc` is not `mm::alloc`
+    // fn test_qualified_disallowed_calls() {
+    //     #[allow(dead_code)]
+    //     #[calls("mm::alloc")]
+    //     pub fn my_function() {
+    //         heap::alloc();
+    //     }
+    // }
+}
+
+// Every test hides the required call inside a different kind of expression.
+mod coverage_tests {
+    pub fn value() -> i32 { 1 }
+    pub fn option() -> Option<i32> { Some(1) }
+    pub fn consume(_value: i32) {}
+
+    pub struct Point {
+        pub x: i32,
+    }
+
+    impl Point {
+        pub fn get(&self) -> i32 { self.x }
+    }
+
+    pub fn point() -> Point { Point { x: 0 } }
+
+    #[test]
+    fn test_match_arm_calls() {
+        #[calls("value")]
+        fn my_function(flag: bool) -> i32 {
+            match flag {
+                true => value(),
+                false => 0,
+            }
+        }
+
+        assert_eq!(my_function(true), 1);
+    }
+
+    #[test]
+    fn test_match_guard_calls() {
+        #[calls("value")]
+        fn my_function(input: i32) -> i32 {
+            match input {
+                x if x == value() => x,
+                _ => 0,
+            }
+        }
+
+        assert_eq!(my_function(1), 1);
+    }
+
+    #[test]
+    fn test_loop_calls() {
+        #[calls("value")]
+        fn my_function() -> i32 {
+            let mut count = 0;
+            loop {
+                count += 1;
+                if count > 2 {
+                    break value();
+                }
+            }
+        }
+
+        assert_eq!(my_function(), 1);
+    }
+
+    #[test]
+    fn test_return_calls() {
+        #[calls("value")]
+        fn my_function(flag: bool) -> i32 {
+            if flag {
+                return value();
+            }
+            0
+        }
+
+        assert_eq!(my_function(true), 1);
+    }
+
+    #[test]
+    fn test_try_calls() {
+        #[calls("option")]
+        fn my_function() -> Option<i32> {
+            let x = option()?;
+            Some(x)
+        }
+
+        assert_eq!(my_function(), Some(1));
+    }
+
+    #[test]
+    fn test_argument_calls() {
+        #[calls("consume", "value")]
+        fn my_function() {
+            consume(value());
+        }
+
+        my_function();
+    }
+
+    #[test]
+    fn test_receiver_calls() {
+        #[calls("point", "get")]
+        fn my_function() -> i32 {
+            point().get()
+        }
+
+        assert_eq!(my_function(), 0);
+    }
+
+    #[test]
+    fn test_binary_calls() {
+        #[calls("value")]
+        fn my_function() -> i32 {
+            1 + value() * 2
+        }
+
+        assert_eq!(my_function(), 3);
+    }
+
+    #[test]
+    fn test_struct_literal_calls() {
+        #[calls("value")]
+        fn my_function() -> Point {
+            Point { x: value() }
+        }
+
+        assert_eq!(my_function().x, 1);
+    }
+
+    #[test]
+    #[allow(unsafe_code)]
+    fn test_unsafe_block_calls() {
+        #[calls("value")]
+        fn my_function() -> i32 {
+            #[allow(unused_unsafe)]
+            unsafe { value() }
+        }
+
+        assert_eq!(my_function(), 1);
+    }
+
+    #[test]
+    fn test_async_block_calls() {
+        #[calls("value")]
+        fn my_function() {
+            let _future = async { value() };
+        }
+
+        my_function();
+    }
+
+    #[test]
+    fn test_let_else_calls() {
+        #[calls("option", "value")]
+        fn my_function() -> i32 {
+            let Some(x) = option() else {
+                return value();
+            };
+            x
+        }
+
+        assert_eq!(my_function(), 1);
+    }
+
+    #[test]
+    fn test_array_and_tuple_calls() {
+        #[calls("value", "option")]
+        fn my_function() -> ([i32; 2], (i32, Option<i32>)) {
+            ([value(), 2], (3, option()))
+        }
+
+        assert_eq!(my_function().0, [1, 2]);
+    }
+
+    #[test]
+    fn test_else_if_calls() {
+        // Regression test: `else if` chains used to overflow the macro stack.
+        #[calls("value", "consume")]
+        fn my_function(input: i32) {
+            if input == 0 {
+            } else if input == 1 {
+                consume(0);
+            } else {
+                let _ = value();
+            }
+        }
+
+        my_function(1);
+    }
+
+    #[test]
+    fn test_long_else_if_calls() {
+        #[calls(only, "value", "consume")]
+        fn my_function(input: i32) {
+            if input == 0 {
+                consume(0);
+            } else if input == 1 {
+                consume(1);
+            } else if input == 2 {
+                consume(2);
+            } else if input == 3 {
+                consume(value());
+            }
+        }
+
+        my_function(3);
+    }
+
+    #[test]
+    fn test_nested_item_calls() {
+        // ``` fails, calls inside nested functions do not count
+        // #[calls("value")]
+        // fn my_function() {
+        //     fn inner() -> i32 { value() }
+        // }
+
+        #[calls(only, "inner")]
+        fn my_function() -> i32 {
+            fn inner() -> i32 { value() }
+            inner()
+        }
+
+        assert_eq!(my_function(), 1);
+    }
+}
+
+mod macro_tests {
+    use core::fmt::Write;
+
+    pub fn value() -> i32 { 1 }
+    pub fn other() -> i32 { 2 }
+
+    macro_rules! twice {
+        ($a:expr, $b:expr) => { $a + $b };
+    }
+
+    pub struct Sink;
+
+    impl Write for Sink {
+        fn write_str(&mut self, _s: &str) -> core::fmt::Result { Ok(()) }
+    }
+
+    #[test]
+    fn test_calls_inside_std_macros() {
+        #[calls("value", "other")]
+        fn my_function() {
+            assert_eq!(value(), 1);
+            assert!(other() == 2, "unexpected {}", other());
+        }
+
+        my_function();
+    }
+
+    #[test]
+    fn test_calls_inside_write_macro() {
+        #[calls("value")]
+        fn my_function(sink: &mut Sink) -> core::fmt::Result {
+            write!(sink, "{}", value())
+        }
+
+        assert!(my_function(&mut Sink).is_ok());
+    }
+
+    #[test]
+    fn test_calls_inside_user_macros() {
+        #[calls(only, "value", "other")]
+        fn my_function() -> i32 {
+            twice!(value(), other())
+        }
+
+        assert_eq!(my_function(), 3);
+    }
+
+    #[test]
+    fn test_required_macros() {
+        #[calls("assert!", "core::assert_eq!")]
+        fn my_function() {
+            assert!(value() == 1);
+            core::assert_eq!(other(), 2);
+        }
+
+        my_function();
+    }
+
+    #[test]
+    fn test_restricted_macros() {
+        #[nocalls("panic!", "other")]
+        fn my_function() -> i32 {
+            // ``` fails if uncommented
+            // if value() == 0 { panic!("unexpected {}", other()); }
+            // debug_assert!(other() == 2);
+            value()
+        }
+
+        assert_eq!(my_function(), 1);
+    }
+}
+
+mod cardinality_tests {
+    pub fn lock() {}
+    pub fn unlock() {}
+    pub fn flush() {}
+    pub fn alloc() {}
+
+    #[test]
+    fn test_exact_calls() {
+        #[calls("lock" == 1, "unlock" == 1)]
+        fn my_function() {
+            lock();
+            unlock();
+            // ``` fails if uncommented, the lock is taken twice
+            // lock();
+        }
+
+        my_function();
+    }
+
+    #[test]
+    fn test_bounded_calls() {
+        #[calls("flush" >= 1, "alloc" <= 2, "lock" < 1, "unlock" > 1)]
+        fn my_function(flag: bool) {
+            alloc();
+            if flag {
+                alloc();
+            }
+            flush();
+            flush();
+            unlock();
+            unlock();
+        }
+
+        my_function(true);
+    }
+
+    #[test]
+    fn test_mixed_calls() {
+        // Entries without a constraint are still required at least once.
+        #[calls(only, "lock" == 1, "unlock" == 1, "flush")]
+        fn my_function() {
+            lock();
+            flush();
+            unlock();
+        }
+
+        my_function();
+    }
+
+    // ``` fails, `alloc` is called three times
+    // #[calls("alloc" <= 2)]
+    // fn my_function() {
+    //     alloc();
+    //     alloc();
+    //     alloc();
+    // }
+}
+
+mod all_paths_tests {
+    pub fn acquire() {}
+    pub fn release() {}
+    pub fn check(input: i32) -> Result<(), i32> {
+        if input < 0 { Err(input) } else { Ok(()) }
+    }
+
+    #[test]
+    fn test_release_on_all_branches() {
+        #[calls(on_all_paths, "release")]
+        fn my_function(input: i32) {
+            if input == 0 {
+                release();
+            } else if input == 1 {
+                acquire();
+                release();
+            } else {
+                match input {
+                    2 => release(),
+                    _ => {
+                        release();
+                    }
+                }
+            }
+        }
+
+        my_function(2);
+    }
+
+    #[test]
+    fn test_release_before_early_exit() {
+        #[calls(on_all_paths, "release")]
+        fn my_function(input: i32) -> Result<(), i32> {
+            acquire();
+            if input == 0 {
+                release();
+                return Ok(());
+            }
+            if let Err(code) = check(input) {
+                release();
+                return Err(code);
+            }
+            release();
+            // ``` fails if uncommented, the error path returns before the release
+            // check(input)?;
+            Ok(())
+        }
+
+        assert!(my_function(1).is_ok());
+    }
+
+    #[test]
+    fn test_release_inside_loop() {
+        // A `loop` body always runs, and the only way out is the `break`.
+        #[calls(on_all_paths, "release" == 1)]
+        fn my_function(input: i32) {
+            let mut count = 0;
+            loop {
+                count += 1;
+                if count < input {
+                    continue;
+                }
+                release();
+                break;
+            }
+        }
+
+        my_function(3);
+    }
+
+    #[test]
+    fn test_paths_with_panic() {
+        #[calls(only, on_all_paths, "acquire", "release")]
+        fn my_function(input: i32) {
+            acquire();
+            if input < 0 {
+                panic!("negative input");
+            }
+            release();
+        }
+
+        my_function(0);
+    }
+
+    // ``` fails, calls inside `while false` or a missing `else` are not on every path
+    // #[calls(on_all_paths, "release")]
+    // fn my_function(input: i32) {
+    //     while false {
+    //         release();
+    //     }
+    //     if input == 0 {
+    //         release();
+    //     }
+    // }
+}
+
+mod receiver_tests {
+    pub struct Frames {
+        pub count: usize,
+    }
+
+    impl Frames {
+        pub fn push(&mut self) { self.count += 1; }
+    }
+
+    pub struct Allocator {
+        pub frames: Frames,
+    }
+
+    pub struct Unrelated;
+
+    impl Unrelated {
+        pub fn push(&mut self) {}
+        pub fn flush(&self) {}
+    }
+
+    impl Allocator {
+        pub fn flush(&self) {}
+
+        #[calls("Self::flush", "self.frames.push")]
+        pub fn allocate(&mut self) {
+            self.frames.push();
+            self.flush();
+        }
+
+        #[calls(only, "Allocator::flush", "Unrelated::push")]
+        pub fn typed_parameters(&self, unrelated: &mut Unrelated) {
+            self.flush();
+            unrelated.push();
+            // ``` fails if uncommented, `Unrelated::flush` is not whitelisted
+            // unrelated.flush();
+        }
+
+        // ``` fails, the same-named method of an unrelated type does not count
+        // #[calls("Self::flush", "self.frames.push")]
+        // pub fn unrelated_calls(&self, unrelated: &mut Unrelated) {
+        //     unrelated.flush();
+        //     unrelated.push();
+        // }
+    }
+
+    #[test]
+    fn test_self_receiver() {
+        let mut allocator = Allocator { frames: Frames { count: 0 } };
+        allocator.allocate();
+        allocator.typed_parameters(&mut Unrelated);
+        Unrelated.flush();
+        assert_eq!(allocator.frames.count, 1);
+    }
+
+    #[test]
+    fn test_typed_let_receiver() {
+        #[calls(only, "Frames::push")]
+        fn my_function() -> usize {
+            let mut frames: Frames = Frames { count: 0 };
+            frames.push();
+            {
+                // Shadowed bindings forget the outer type.
+                let mut frames: Unrelated = Unrelated;
+                // ``` fails if uncommented, `frames` is `Unrelated` in this scope
+                // frames.flush();
+                let _ = &mut frames;
+            }
+            frames.count
+        }
+
+        assert_eq!(my_function(), 1);
+    }
+
+    #[test]
+    fn test_unknown_receiver() {
+        // Receivers without a known type are matched by the method name.
+        #[calls("Frames::push")]
+        fn my_function(allocator: &mut Allocator) {
+            allocator.frames.push();
+        }
+
+        let mut allocator = Allocator { frames: Frames { count: 0 } };
+        my_function(&mut allocator);
+        assert_eq!(allocator.frames.count, 1);
+    }
+}
+
+mod item_tests {
+    use super::*;
+
+    pub struct Driver {
+        pub enabled: bool,
+    }
+
+    // Every method of the impl block shares the policy.
+    #[calls("Self::check")]
+    impl Driver {
+        #[calls(skip)]
+        pub fn check(&self) -> bool {
+            self.enabled
+        }
+
+        pub fn read(&self) -> bool {
+            self.check()
+        }
+
+        pub fn write(&self) -> bool {
+            self.check() && self.enabled
+        }
+
+        // A method with its own attribute overrides the impl policy.
+        #[calls("allowed_function")]
+        pub fn reset(&self) {
+            allowed_function();
+        }
+
+        // ``` fails if uncommented, the method never checks the driver
+        // pub fn enable(&mut self) {
+        //     self.enabled = true;
+        // }
+    }
+
+    #[calls("allowed_function")]
+    pub trait Device {
+        fn probe(&self) {
+            allowed_function();
+        }
+
+        // Methods without a default body are not checked.
+        fn remove(&self);
+    }
+
+    impl Device for Driver {
+        fn remove(&self) {}
+    }
+
+    #[nocalls("disallowed_function")]
+    mod handlers {
+        use super::*;
+
+        pub fn on_read() {
+            allowed_function();
+        }
+
+        pub mod nested {
+            // ``` fails if uncommented, nested inline modules share the policy
+            // pub fn on_write() {
+            //     super::super::disallowed_function();
+            // }
+        }
+
+        #[nocalls(skip)]
+        pub fn on_error() {
+            disallowed_function();
+        }
+    }
+
+    #[test]
+    fn test_impl_policy() {
+        let driver = Driver { enabled: true };
+        assert!(driver.read());
+        assert!(driver.write());
+        driver.reset();
+    }
+
+    #[test]
+    fn test_trait_policy() {
+        let driver = Driver { enabled: false };
+        driver.probe();
+        driver.remove();
+    }
+
+    #[test]
+    fn test_module_policy() {
+        handlers::on_read();
+        handlers::on_error();
+    }
+}
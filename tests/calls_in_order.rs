@@ -0,0 +1,206 @@
+#![no_std]
+#![deny(unsafe_code)]
+
+#[macro_use]
+extern crate proc_assertions;
+
+pub fn lock() {}
+pub fn update_entry() {}
+pub fn flush_tlb() {}
+pub fn unlock() {}
+pub fn check() -> Result<(), i32> { Ok(()) }
+
+pub struct PageTable;
+
+impl PageTable {
+    pub fn lock(&self) {}
+    pub fn unlock(&self) {}
+
+    #[calls_in_order("lock", "unlock")]
+    pub fn locked_update(&self) {
+        self.lock();
+        update_entry();
+        self.unlock();
+    }
+
+    // ``` fails, the TLB is flushed before the entry is updated
+    // #[calls_in_order("lock", "update_entry", "flush_tlb", "unlock")]
+    // pub fn unordered_update(&self) {
+    //     self.lock();
+    //     flush_tlb();
+    //     update_entry();
+    //     self.unlock();
+    // }
+}
+
+#[cfg(test)]
+mod simple_tests {
+    use super::*;
+
+    #[test]
+    fn test_ordered_calls() {
+        #[calls_in_order("lock", "update_entry", "flush_tlb", "unlock")]
+        fn my_function() {
+            lock();
+            update_entry();
+            flush_tlb();
+            unlock();
+        }
+
+        my_function();
+    }
+
+    #[test]
+    fn test_repeated_step() {
+        // The current step may repeat before moving on to the next one.
+        #[calls_in_order("lock", "update_entry", "flush_tlb", "unlock")]
+        fn my_function() {
+            lock();
+            update_entry();
+            update_entry();
+            flush_tlb();
+            unlock();
+            // ``` fails if uncommented, the entry is updated after the flush
+            // update_entry();
+        }
+
+        my_function();
+    }
+
+    #[test]
+    fn test_unlisted_calls() {
+        #[calls_in_order("lock", "unlock")]
+        fn my_function() {
+            update_entry();
+            lock();
+            flush_tlb();
+            unlock();
+            update_entry();
+        }
+
+        my_function();
+    }
+
+    #[test]
+    fn test_page_table_order() {
+        PageTable.locked_update();
+    }
+}
+
+mod branch_tests {
+    use super::*;
+
+    #[test]
+    fn test_ordered_branches() {
+        // Every branch keeps the order on its own.
+        #[calls_in_order("lock", "update_entry", "unlock")]
+        fn my_function(input: i32) {
+            lock();
+            if input == 0 {
+                update_entry();
+            } else if input == 1 {
+                update_entry();
+                update_entry();
+            } else {
+                update_entry();
+            }
+            match input {
+                0 => unlock(),
+                _ => {
+                    unlock();
+                }
+            }
+        }
+
+        my_function(1);
+    }
+
+    #[test]
+    fn test_early_return() {
+        #[calls_in_order("lock", "unlock")]
+        fn my_function(input: i32) -> Result<(), i32> {
+            lock();
+            if input == 0 {
+                unlock();
+                return Ok(());
+            }
+            // ``` fails if uncommented, this path returns while locked
+            // check()?;
+            unlock();
+            check()
+        }
+
+        assert!(my_function(0).is_ok());
+    }
+
+    #[test]
+    fn test_diverging_path() {
+        // Paths ending in a panic never reach the function exit.
+        #[calls_in_order("lock", "unlock")]
+        fn my_function(input: i32) {
+            lock();
+            if input < 0 {
+                panic!("negative input");
+            }
+            unlock();
+        }
+
+        my_function(1);
+    }
+
+    // ``` fails, the `else` branch never unlocks
+    // #[calls_in_order("lock", "unlock")]
+    // fn my_function(input: i32) {
+    //     lock();
+    //     if input == 0 {
+    //         unlock();
+    //     }
+    // }
+}
+
+mod loop_tests {
+    use super::*;
+
+    #[test]
+    fn test_loop_inside_steps() {
+        #[calls_in_order("lock", "update_entry", "unlock")]
+        fn my_function(count: i32) {
+            lock();
+            update_entry();
+            for _ in 0..count {
+                update_entry();
+            }
+            unlock();
+        }
+
+        my_function(3);
+    }
+
+    #[test]
+    fn test_loop_with_break() {
+        #[calls_in_order("lock", "update_entry", "unlock")]
+        fn my_function(count: i32) {
+            lock();
+            let mut done = 0;
+            loop {
+                update_entry();
+                done += 1;
+                if done >= count {
+                    break;
+                }
+            }
+            unlock();
+        }
+
+        my_function(3);
+    }
+
+    // ``` fails, the loop may not run and the second iteration locks after unlocking
+    // #[calls_in_order("lock", "unlock")]
+    // fn my_function(count: i32) {
+    //     for _ in 0..count {
+    //         lock();
+    //         unlock();
+    //     }
+    // }
+}
@@ -0,0 +1,109 @@
+#![no_std]
+#![deny(unsafe_code)]
+
+#[macro_use]
+extern crate proc_assertions;
+
+#[enforce_mutations]
+mod frames {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum State {
+        #[default]
+        Free,
+        Mapped,
+    }
+
+    #[derive(Default)]
+    pub struct Frame {
+        #[mutated_only_by("map", "reset")]
+        pub state: State,
+        #[mutated_only_by()]
+        pub number: usize,
+        pub refs: usize,
+    }
+
+    impl Frame {
+        pub fn new(number: usize) -> Self {
+            Frame { number, ..Frame::default() }
+        }
+
+        pub fn map(&mut self) {
+            self.state = State::Mapped;
+            self.refs += 1;
+        }
+
+        pub fn unmap(&mut self) {
+            self.refs -= 1;
+            // ``` fails if uncommented, `unmap` is not allowed to change the state
+            // self.state = State::Free;
+            // ``` fails if uncommented, no function may change the number
+            // self.number = 0;
+        }
+
+        pub fn is_mapped(&self) -> bool {
+            self.state == State::Mapped
+        }
+    }
+
+    pub fn reset(frame: &mut Frame) {
+        frame.state = State::Free;
+        frame.refs = 0;
+    }
+
+    // Nested inline modules are part of the scope.
+    pub mod debug {
+        use super::Frame;
+
+        pub fn poke(frame: &mut Frame) -> usize {
+            frame.refs += 1;
+            // ``` fails if uncommented, `poke` is not allowed to change the state
+            // *frame = Frame::new(frame.number);
+            // let state = &mut frame.state;
+            frame.number
+        }
+    }
+}
+
+mod tuple_tests {
+    #[enforce_mutations]
+    pub mod counters {
+        #[derive(Default)]
+        pub struct Counter(#[mutated_only_by("increment")] pub u32, pub u32);
+
+        pub fn increment(counter: &mut Counter) {
+            counter.0 += 1;
+        }
+
+        pub fn touch(counter: &mut Counter) {
+            counter.1 += 1;
+            // ``` fails if uncommented, only `increment` changes the count
+            // counter.0 = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::frames::{debug, reset, Frame, State};
+    use super::tuple_tests::counters::{increment, touch, Counter};
+
+    #[test]
+    fn test_field_policy() {
+        let mut frame = Frame::new(7);
+        frame.map();
+        assert!(frame.is_mapped());
+        frame.unmap();
+        assert_eq!(debug::poke(&mut frame), 7);
+        reset(&mut frame);
+        assert_eq!(frame.state, State::Free);
+        assert_eq!(frame.refs, 0);
+    }
+
+    #[test]
+    fn test_tuple_fields() {
+        let mut counter = Counter::default();
+        increment(&mut counter);
+        touch(&mut counter);
+        assert_eq!((counter.0, counter.1), (1, 1));
+    }
+}
@@ -1,5 +1,6 @@
 #![no_std]
 #![deny(unsafe_code)]
+#![allow(clippy::no_effect, clippy::field_reassign_with_default)]
 
 #[macro_use]
 extern crate proc_assertions;
@@ -86,3 +87,962 @@ mod nested_tests {
         name();
     }
 }
+
+// Regression tests: `else if` chains used to overflow the macro stack.
+mod else_if_tests {
+    use super::*;
+
+    #[test]
+    fn test_else_if_mutate() {
+        #[mutates(MyStruct: ("field"))]
+        fn my_function(instance: &mut MyStruct, input: i32) {
+            if input == 0 {
+                instance.field = 0;
+            } else if input == 1 {
+                instance.field += 1;
+            } else if input == 2 {
+                instance.field -= 2;
+            } else {
+                instance.field = input;
+            }
+        }
+
+        let mut instance = MyStruct::default();
+        my_function(&mut instance, 1);
+        assert_eq!(instance.field, 1);
+    }
+}
+
+mod item_tests {
+    use super::*;
+
+    #[mutates(MyStruct: ("field"))]
+    impl MyStruct {
+        pub fn increment(&mut self) {
+            self.field += 1;
+        }
+
+        pub fn reset(&mut self) {
+            self.field = 0;
+        }
+    }
+
+    #[test]
+    fn test_impl_policy() {
+        let mut instance = MyStruct::default();
+        instance.increment();
+        instance.reset();
+        assert_eq!(instance.field, 0);
+    }
+}
+
+mod classification_tests {
+    use core::mem;
+
+    #[derive(Default)]
+    pub struct Buffer {
+        pub len: usize,
+        pub data: [u8; 4],
+        pub last: Option<u8>,
+    }
+
+    impl Buffer {
+        // Reading the fields in comparisons and arithmetic is not a mutation.
+        #[mutates(Buffer: ("len"))]
+        pub fn grow(&mut self) -> bool {
+            if self.len < self.data.len() && self.len + 1 != 0 {
+                self.len += 1;
+            }
+            // ``` fails if uncommented, `&mut self` methods mutate the field
+            // self.data.fill(0);
+            self.len == self.data.len()
+        }
+
+        #[mutates(Buffer: ("data", "last"))]
+        pub fn rotate(&mut self) {
+            self.data.rotate_left(1);
+            self.last.take();
+            // ``` fails if uncommented, the field is borrowed mutably
+            // let _len = &mut self.len;
+        }
+
+        #[mutates(Buffer: ("len", "last"))]
+        pub fn swap_last(&mut self, other: &mut Option<u8>) {
+            mem::swap(&mut self.last, other);
+            let previous = mem::replace(&mut self.len, 0);
+            self.len = previous + usize::from(self.last.is_some());
+            // ``` fails if uncommented, `data` is not whitelisted
+            // let _ = mem::take(&mut self.data);
+        }
+
+        // The wildcard allows overwriting the whole instance.
+        #[mutates(Buffer: ("*"))]
+        pub fn reset(&mut self) {
+            *self = Buffer::default();
+            // ``` fails without the wildcard, e.g. with #[mutates(Buffer: ("len"))]
+        }
+    }
+
+    #[test]
+    fn test_classification() {
+        let mut buffer = Buffer::default();
+        assert!(!buffer.grow());
+        buffer.rotate();
+        buffer.swap_last(&mut Some(1));
+        assert_eq!(buffer.len, 2);
+        buffer.reset();
+        assert_eq!(buffer.len, 0);
+    }
+}
+
+mod path_tests {
+    #[derive(Default)]
+    pub struct Counter {
+        pub count: u32,
+        pub limit: u32,
+    }
+
+    #[derive(Default)]
+    pub struct Entry(pub u32, pub bool);
+
+    #[derive(Default)]
+    pub struct Table {
+        pub inner: Counter,
+        pub entries: [Entry; 4],
+    }
+
+    #[derive(Default)]
+    pub struct Pair(pub u32, pub u32);
+
+    impl Table {
+        #[mutates(Table: ("inner.count", "entries[].1"))]
+        pub fn invalidate(&mut self, index: usize) {
+            self.inner.count += 1;
+            self.entries[index].1 = false;
+            // ``` fails if uncommented, only `inner.count` is whitelisted
+            // self.inner.limit = 0;
+            // self.inner = Counter::default();
+            // self.entries[index].0 = 0;
+        }
+
+        // A parent path whitelists everything nested inside it.
+        #[mutates(Table: ("inner", "entries"))]
+        pub fn reset(&mut self) {
+            self.inner.limit = 8;
+            self.inner.count = 0;
+            self.entries[0] = Entry::default();
+        }
+    }
+
+    impl Pair {
+        #[mutates(Pair: ("0"))]
+        pub fn bump(&mut self) {
+            self.0 += 1;
+            // ``` fails if uncommented
+            // self.1 += 1;
+        }
+    }
+
+    #[test]
+    fn test_nested_paths() {
+        let mut table = Table::default();
+        table.reset();
+        table.invalidate(1);
+        assert_eq!(table.inner.count, 1);
+        assert!(!table.entries[1].1);
+        assert_eq!(table.entries[1].0, 0);
+    }
+
+    #[test]
+    fn test_tuple_fields() {
+        let mut pair = Pair::default();
+        pair.bump();
+        assert_eq!((pair.0, pair.1), (1, 0));
+    }
+}
+
+mod argument_tests {
+    use super::*;
+
+    // Unrelated argument types and patterns are ignored instead of aborting the macro.
+    #[mutates(MyStruct: ("field"))]
+    fn unrelated(
+        instance: &mut MyStruct,
+        _: u8,
+        values: &[i32],
+        (first, _second): (i32, i32),
+        mut callback: impl FnMut(i32),
+    ) {
+        instance.field = values.len() as i32 + first;
+        callback(instance.field);
+    }
+
+    // Instances inside tuples and destructured fields are tracked.
+    #[mutates(MyStruct: ("field"))]
+    fn destructured((left, _): (&mut MyStruct, u8), MyStruct { field }: &mut MyStruct) {
+        left.field += 1;
+        *field += 1;
+    }
+
+    #[derive(Default)]
+    pub struct Pair {
+        pub first: i32,
+        pub second: i32,
+    }
+
+    #[mutates(Pair: ("first"))]
+    fn destructured_pair(Pair { first, second }: &mut Pair) {
+        *first = *second;
+        // ``` fails if uncommented, `second` is destructured from `Pair::second`
+        // *second = 0;
+    }
+
+    #[test]
+    fn test_argument_patterns() {
+        let mut instance = MyStruct::default();
+        unrelated(&mut instance, 0, &[1, 2], (3, 4), |_| {});
+        assert_eq!(instance.field, 5);
+
+        let mut other = MyStruct::default();
+        destructured((&mut instance, 0), &mut other);
+        assert_eq!((instance.field, other.field), (6, 1));
+
+        let mut pair = Pair { first: 0, second: 2 };
+        destructured_pair(&mut pair);
+        assert_eq!(pair.first, 2);
+    }
+}
+
+mod type_tests {
+    use core::cell::RefCell;
+    use core::pin::Pin;
+
+    #[derive(Default)]
+    pub struct Slot<T> {
+        pub value: T,
+        pub used: bool,
+    }
+
+    pub mod mm {
+        #[derive(Default)]
+        pub struct Frame {
+            pub count: u32,
+        }
+    }
+
+    // Generic arguments and module paths do not hide the struct.
+    #[mutates(Slot: ("value"))]
+    fn fill<T: Copy>(slot: &mut Slot<T>, value: T) {
+        slot.value = value;
+        // ``` fails if uncommented
+        // slot.used = true;
+    }
+
+    // References, `Option`, `Pin` and `RefCell` wrappers are looked through.
+    #[mutates(Frame: ("count"))]
+    fn wrapped(
+        frame: &mut Option<mm::Frame>,
+        pinned: Pin<&mut mm::Frame>,
+        shared: &RefCell<mm::Frame>,
+    ) {
+        frame.as_mut().unwrap().count += 1;
+        pinned.get_mut().count += 1;
+        shared.borrow_mut().count += 1;
+    }
+
+    // ``` fails, the `Frame` inside the `RefCell` is mutated
+    // #[mutates(Frame: ())]
+    // fn unauthorized(shared: &RefCell<mm::Frame>) {
+    //     shared.borrow_mut().count = 0;
+    // }
+
+    // `Self` resolves to the type of the impl block.
+    #[mutates(Slot: ("used"))]
+    impl<T: Copy> Slot<T> {
+        pub fn take_from(&mut self, other: &mut Self) -> T {
+            other.used = false;
+            self.used = true;
+            // ``` fails if uncommented
+            // other.value = self.value;
+            other.value
+        }
+    }
+
+    // `self` is not an instance in an impl block of another type.
+    #[mutates(Slot: ())]
+    impl mm::Frame {
+        pub fn reset(&mut self) {
+            self.count = 0;
+        }
+    }
+
+    #[test]
+    fn test_generic_and_qualified() {
+        let mut slot = Slot::default();
+        fill(&mut slot, 3);
+        let mut other = Slot { value: 4, used: true };
+        assert_eq!(slot.take_from(&mut other), 4);
+        assert!(slot.used && !other.used);
+    }
+
+    #[test]
+    fn test_wrapped() {
+        let mut frame = Some(mm::Frame::default());
+        let mut pinned = mm::Frame::default();
+        let shared = RefCell::new(mm::Frame::default());
+        wrapped(&mut frame, Pin::new(&mut pinned), &shared);
+        assert_eq!(frame.unwrap().count + pinned.count + shared.borrow().count, 3);
+        pinned.reset();
+        assert_eq!(pinned.count, 0);
+    }
+}
+
+mod multiple_struct_tests {
+    #[derive(Default)]
+    pub struct Frame {
+        pub state: u8,
+        pub owner: u8,
+    }
+
+    #[derive(Default)]
+    pub struct Allocator {
+        pub free_list: [u8; 4],
+        pub count: usize,
+    }
+
+    impl Allocator {
+        // `self` is the `Allocator`, as the `Frame` is passed as an argument.
+        #[mutates(Frame: ("state"), Allocator: ("free_list", "count"))]
+        pub fn allocate(&mut self, frame: &mut Frame) {
+            self.count += 1;
+            self.free_list[self.count] = 0;
+            frame.state = 1;
+            // ``` fails if uncommented, `owner` is not whitelisted for `Frame`
+            // frame.owner = 1;
+        }
+    }
+
+    // On the impl block, `self` is known to be the `Frame`.
+    #[mutates(Frame: ("state"), Allocator: ("count"))]
+    impl Frame {
+        pub fn map(&mut self) {
+            self.state = 1;
+        }
+    }
+
+    // ``` fails if uncommented, `self` may be a `Frame` or an `Allocator`
+    // impl Frame {
+    //     #[mutates(Frame: ("state"), Allocator: ("count"))]
+    //     pub fn unmap(&mut self) {
+    //         self.state = 0;
+    //     }
+    // }
+
+    #[test]
+    fn test_multiple_structs() {
+        let mut allocator = Allocator::default();
+        let mut frame = Frame::default();
+        allocator.allocate(&mut frame);
+        assert_eq!((allocator.count, frame.state, frame.owner), (1, 1, 0));
+        frame.map();
+        assert_eq!(frame.state, 1);
+    }
+}
+
+mod scope_tests {
+    #[derive(Default)]
+    pub struct Node {
+        pub value: i32,
+        pub visits: u32,
+        pub child: Option<Child>,
+        pub limit: Option<u32>,
+    }
+
+    #[derive(Default)]
+    pub struct Child {
+        pub value: i32,
+    }
+
+    impl Node {
+        // Writes through references and reborrows count as writes to the original fields.
+        #[mutates(Node: ("visits", "child.value"))]
+        pub fn visit(&mut self) {
+            let node = &mut *self;
+            node.visits += 1;
+            if let Some(child) = self.child.as_mut() {
+                child.value += 1;
+            }
+            // ``` fails if uncommented, `value` is written through an alias
+            // let value = &mut self.value;
+            // *value = 0;
+            // let alias = &mut *self;
+            // alias.value = 0;
+        }
+
+        // A shadowed binding is no longer the instance.
+        #[mutates(Node: ())]
+        pub fn shadowed(&self) -> i32 {
+            let mut value = self.value;
+            {
+                let mut node = Child::default();
+                node.value = 1;
+                value += node.value;
+            }
+            value
+        }
+    }
+
+    #[test]
+    fn test_aliases() {
+        let mut node = Node { child: Some(Child::default()), ..Node::default() };
+        node.visit();
+        assert_eq!((node.visits, node.child.as_ref().unwrap().value), (1, 1));
+        assert_eq!(node.shadowed(), 1);
+    }
+
+    #[test]
+    fn test_shadowed_instance() {
+        #[mutates(Node: ("visits"))]
+        fn my_function(node: &mut Node) -> i32 {
+            node.visits += 1;
+            let node = Child { value: 2 };
+            let mut copy = node.value;
+            copy += 1;
+            copy
+        }
+
+        assert_eq!(my_function(&mut Node::default()), 3);
+    }
+
+    // A value copied out of a field with `unwrap` or `expect` is not the field.
+    #[test]
+    fn test_copied_value() {
+        #[mutates(Node: ("visits"))]
+        fn my_function(node: &mut Node) -> u32 {
+            let mut limit = node.limit.unwrap();
+            limit += 1;
+            let mut expected = node.limit.expect("a limit");
+            expected += 2;
+            node.visits = limit;
+            // ``` fails if uncommented, `as_mut` borrows the field
+            // let limit = node.limit.as_mut().unwrap();
+            // *limit = 0;
+            expected
+        }
+
+        let mut node = Node { limit: Some(1), ..Node::default() };
+        assert_eq!(my_function(&mut node), 3);
+        assert_eq!((node.visits, node.limit), (2, Some(1)));
+    }
+}
+
+mod constructor_tests {
+    #[derive(Default)]
+    pub struct Config {
+        pub size: usize,
+        pub flags: u8,
+    }
+
+    impl Config {
+        pub fn with_size(size: usize) -> Self {
+            Config { size, flags: 0 }
+        }
+
+        pub fn try_new(size: usize) -> Result<Self, ()> {
+            Ok(Config::with_size(size))
+        }
+
+        #[mutates(Config: ("size"))]
+        pub fn doubled(&self) -> Self {
+            let mut config = Self::with_size(self.size);
+            config.size *= 2;
+            // ``` fails if uncommented, `config` is built by `Self::with_size`
+            // config.flags = 1;
+            config
+        }
+    }
+
+    pub fn build() -> Config {
+        Config::default()
+    }
+
+    #[test]
+    fn test_constructors() {
+        #[mutates(Config: ("size"))]
+        fn my_function(large: bool) -> Result<usize, ()> {
+            let mut literal = Config { size: 1, flags: 0 };
+            literal.size += 1;
+            let mut annotated: Config = build();
+            annotated.size += 1;
+            let mut fallible = Config::try_new(4)?;
+            fallible.size += 1;
+            let mut chosen = if large { Config::with_size(8) } else { build() };
+            chosen.size += 1;
+            let mut matched = match large {
+                true => Config::with_size(16),
+                false => Config::default(),
+            };
+            matched.size += 1;
+            // ``` fails if uncommented, every binding above is a `Config`
+            // literal.flags = 1;
+            // annotated.flags = 1;
+            // fallible.flags = 1;
+            // chosen.flags = 1;
+            // matched.flags = 1;
+            Ok(literal.size + annotated.size + fallible.size + chosen.size + matched.size)
+        }
+
+        assert_eq!(my_function(true), Ok(2 + 1 + 5 + 9 + 17));
+        assert_eq!(Config::with_size(2).doubled().size, 4);
+        assert_eq!(build().flags, 0);
+    }
+}
+
+mod interior_tests {
+    use core::cell::{Cell, RefCell};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    pub struct Counter {
+        pub hits: Cell<u32>,
+        pub log: RefCell<[u8; 4]>,
+        pub total: AtomicUsize,
+        pub name: Cell<u32>,
+    }
+
+    impl Counter {
+        // Interior mutability through `&self` is a mutation of the field.
+        #[mutates(Counter: ("hits", "log", "total"))]
+        pub fn hit(&self) {
+            self.hits.set(self.hits.get() + 1);
+            self.log.borrow_mut()[0] = 1;
+            self.total.fetch_add(1, Ordering::Relaxed);
+            self.hits.replace(self.hits.get() + 1);
+            // ``` fails if uncommented, `name` is not whitelisted
+            // self.name.set(1);
+            // self.name.swap(&self.hits);
+            // self.name.replace(1);
+        }
+
+        // Custom methods are listed with `interior_methods`.
+        #[mutates(Counter: ("hits"), interior_methods = ("bump"))]
+        pub fn bump_hits(&self) {
+            bump(&self.hits);
+            self.hits.bump();
+            // ``` fails if uncommented, `bump` mutates `name`
+            // self.name.bump();
+        }
+
+        // By-value receivers are instances too.
+        #[mutates(Counter: ("total"))]
+        pub fn finish(self) -> usize {
+            self.total.store(0, Ordering::Relaxed);
+            self.total.into_inner() + self.name.get() as usize
+        }
+    }
+
+    #[derive(Default)]
+    pub struct Levels {
+        pub count: u32,
+        pub peak: u32,
+    }
+
+    #[derive(Default)]
+    pub struct Gauge {
+        pub levels: RefCell<Levels>,
+    }
+
+    impl Gauge {
+        // Fields behind a lock are listed by their nested path.
+        #[mutates(Gauge: ("levels.count"))]
+        pub fn raise(&self) {
+            self.levels.borrow_mut().count += 1;
+            let mut levels = self.levels.borrow_mut();
+            levels.count += 1;
+            // ``` fails if uncommented, `levels.peak` is not whitelisted
+            // levels.peak = levels.count;
+            // self.levels.borrow_mut().peak = 0;
+        }
+    }
+
+    pub trait Bump {
+        fn bump(&self);
+    }
+
+    impl Bump for Cell<u32> {
+        fn bump(&self) {
+            self.set(self.get() + 1);
+        }
+    }
+
+    fn bump(cell: &Cell<u32>) {
+        cell.bump();
+    }
+
+    #[test]
+    fn test_interior_mutability() {
+        let counter = Counter {
+            hits: Cell::new(0),
+            log: RefCell::new([0; 4]),
+            total: AtomicUsize::new(0),
+            name: Cell::new(0),
+        };
+        counter.hit();
+        counter.bump_hits();
+        assert_eq!(counter.hits.get(), 4);
+        assert_eq!(counter.log.borrow()[0], 1);
+        assert_eq!(counter.finish(), 0);
+
+        let gauge = Gauge::default();
+        gauge.raise();
+        assert_eq!((gauge.levels.borrow().count, gauge.levels.borrow().peak), (2, 0));
+    }
+}
+
+mod pointer_tests {
+    use core::ptr;
+
+    #[derive(Default)]
+    pub struct Page {
+        pub data: u64,
+        pub flags: u64,
+    }
+
+    impl Page {
+        #[allow(unsafe_code, clippy::deref_addrof)]
+        #[mutates(Page: ("data"))]
+        pub fn write(&mut self, value: u64) {
+            unsafe {
+                ptr::write(ptr::addr_of_mut!(self.data), value);
+                let data = &mut self.data as *mut u64;
+                *data += 1;
+                *(&raw mut self.data) += 1;
+                // ``` fails if uncommented, a mutable raw pointer to `flags` escapes
+                // let flags = ptr::addr_of_mut!(self.flags);
+                // let flags = &raw mut self.flags;
+                // let flags = &self.flags as *const u64 as *mut u64;
+                // ptr::write(&mut self.flags, value);
+                // *(&raw mut self.flags) = value;
+            }
+        }
+
+        #[mutates(Page: ())]
+        pub fn read(&self) -> u64 {
+            let data = &raw const self.data;
+            let flags = ptr::addr_of!(self.flags);
+            #[allow(unsafe_code)]
+            unsafe { *data + *flags }
+        }
+    }
+
+    #[test]
+    fn test_raw_pointers() {
+        let mut page = Page::default();
+        page.write(1);
+        assert_eq!(page.read(), 3);
+    }
+}
+
+mod pattern_tests {
+    pub enum State {
+        Idle(u32),
+        Busy { ticks: u32 },
+    }
+
+    #[derive(Default)]
+    pub struct Entry {
+        pub valid: bool,
+        pub age: u32,
+    }
+
+    pub struct Machine {
+        pub count: u32,
+        pub state: State,
+        pub cached: Option<u32>,
+        pub entries: [Entry; 2],
+        pub name: Option<u32>,
+    }
+
+    impl Machine {
+        // Bindings destructured from an instance refer to its fields.
+        #[mutates(Machine: ("count", "state", "cached", "entries[].valid"))]
+        pub fn step(&mut self) {
+            let Machine { count, .. } = self;
+            *count += 1;
+            match &mut self.state {
+                State::Idle(ticks) => *ticks += 1,
+                State::Busy { ticks } if *ticks > 0 => *ticks -= 1,
+                State::Busy { .. } => {}
+            }
+            if let Some(ref mut value) = self.cached {
+                *value = 0;
+            }
+            for entry in self.entries.iter_mut() {
+                entry.valid = false;
+            }
+            for (_, entry) in self.entries.iter_mut().enumerate().rev() {
+                entry.valid = true;
+            }
+            // A copied field is not the field.
+            if let Some(mut name) = self.name {
+                name += 1;
+                self.count += name;
+            }
+            // ``` fails if uncommented
+            // let Machine { name, .. } = self;
+            // *name = None;
+            // if let Some(ref mut name) = self.name { *name = 0; }
+            // match self.name { Some(ref mut name) => *name = 0, None => {} }
+            // for entry in &mut self.entries { entry.age = 1; }
+            // for (_, entry) in self.entries.iter_mut().enumerate() { entry.age = 1; }
+        }
+    }
+
+    #[test]
+    fn test_patterns() {
+        let mut machine = Machine {
+            count: 0,
+            state: State::Idle(0),
+            cached: Some(3),
+            entries: [Entry::default(), Entry::default()],
+            name: Some(1),
+        };
+        machine.step();
+        assert_eq!(machine.count, 3);
+        assert!(matches!(machine.state, State::Idle(1)));
+        assert_eq!(machine.cached, Some(0));
+        assert!(machine.entries.iter().all(|entry| entry.valid && entry.age == 0));
+        machine.state = State::Busy { ticks: 2 };
+        machine.step();
+        assert_eq!(machine.count, 6);
+        assert!(matches!(machine.state, State::Busy { ticks: 1 }));
+        assert_eq!(machine.name, Some(1));
+    }
+}
+
+mod control_flow_tests {
+    #[derive(Default)]
+    pub struct Walker {
+        pub steps: u32,
+        pub marks: [u32; 2],
+        pub other: u32,
+    }
+
+    fn identity(value: u32) -> u32 {
+        value
+    }
+
+    impl Walker {
+        // Mutations are found inside every kind of expression.
+        #[allow(clippy::never_loop)]
+        #[mutates(Walker: ("steps", "marks"))]
+        pub fn walk(&mut self, input: Option<u32>) -> u32 {
+            let Some(value) = input else {
+                self.steps += 1;
+                return { self.steps += 1; 0 };
+            };
+            let total = loop {
+                self.steps += 1;
+                break { self.steps += 1; value };
+            };
+            identity({ self.steps += 1; total });
+            let _pair = ({ self.steps += 1; 1 }, [{ self.marks[0] += 1; 2 }]);
+            self.marks[{ self.steps += 1; 1 }] = 1;
+            assert!({ self.steps += 1; true });
+            let _future = async { self.steps += 1 };
+            match input {
+                Some(0) => self.marks[0] += 1,
+                Some(_) => self.steps += 1,
+                None => {}
+            }
+            #[allow(unsafe_code, unused_unsafe)]
+            unsafe { self.marks[1] += 1 };
+            let _nothing: ();
+            // ``` fails if uncommented
+            // let Some(_) = input else { self.other = 1; return 0; };
+            // return { self.other = 1; 0 };
+            // loop { self.other = 1; break; }
+            // loop { break { self.other = 1; 0 }; };
+            // identity({ self.other = 1; 0 });
+            // let _pair = ({ self.other = 1; 1 }, [{ self.other = 1; 2 }]);
+            // self.marks[{ self.other = 1; 0 }] = 1;
+            // assert!({ self.other = 1; true });
+            // let _future = async { self.other = 1 };
+            // match input { Some(0) => {} Some(_) => self.other = 1, None => {} }
+            // #[allow(unsafe_code, unused_unsafe)]
+            // unsafe { self.other = 1 };
+            self.steps + self.other
+        }
+    }
+
+    #[test]
+    fn test_control_flow() {
+        let mut walker = Walker::default();
+        assert_eq!(walker.walk(None), 0);
+        assert_eq!(walker.steps, 2);
+        assert_eq!(walker.walk(Some(3)), 9);
+        assert_eq!(walker.marks, [1, 2]);
+    }
+}
+
+mod policy_tests {
+    // Policies may be used before the struct is declared.
+    impl Allocator {
+        #[mutates(Allocator::alloc)]
+        pub fn alloc(&mut self) -> Option<u32> {
+            self.count += 1;
+            // ``` fails if uncommented, `alloc` does not list `peak`
+            // self.peak = self.count;
+            self.free_list.pop()
+        }
+    }
+
+    #[derive(Default)]
+    #[mutation_policies(alloc = ("free_list", "count"), reset = ("*"))]
+    pub struct Allocator {
+        pub free_list: Stack,
+        pub count: u32,
+        pub peak: u32,
+    }
+
+    #[mutates(Allocator::reset)]
+    impl Allocator {
+        pub fn reset(&mut self) {
+            *self = Allocator::default();
+        }
+
+        pub fn free(&mut self, frame: u32) {
+            self.free_list.push(frame);
+            self.peak = self.peak.max(self.count);
+        }
+    }
+
+    // ``` fails if uncommented, the policy is unknown
+    // #[mutates(Allocator::free)]
+    // pub fn free(_allocator: &mut Allocator) {}
+
+    #[derive(Default)]
+    pub struct Stack {
+        frames: [u32; 4],
+        len: usize,
+    }
+
+    impl Stack {
+        pub fn push(&mut self, frame: u32) {
+            self.frames[self.len] = frame;
+            self.len += 1;
+        }
+
+        pub fn pop(&mut self) -> Option<u32> {
+            self.len = self.len.checked_sub(1)?;
+            Some(self.frames[self.len])
+        }
+    }
+
+    mod users {
+        // Policies are reached through the path of the struct.
+        #[mutates(super::Allocator::alloc)]
+        pub fn alloc_twice(allocator: &mut super::Allocator) -> Option<u32> {
+            allocator.count += 1;
+            allocator.alloc()
+        }
+    }
+
+    mod imports {
+        use super::Allocator;
+
+        // Importing the struct also imports its policies.
+        #[mutates(Allocator::reset)]
+        pub fn clear(allocator: &mut Allocator) {
+            allocator.reset();
+            allocator.peak = 0;
+        }
+    }
+
+    #[test]
+    fn test_policies() {
+        let mut allocator = Allocator::default();
+        allocator.free(7);
+        assert_eq!(users::alloc_twice(&mut allocator), Some(7));
+        assert_eq!(allocator.count, 2);
+        allocator.reset();
+        assert_eq!(allocator.count, 0);
+        imports::clear(&mut allocator);
+        assert_eq!(allocator.peak, 0);
+    }
+}
+
+mod field_name_tests {
+    #[derive(Default)]
+    pub struct Record<T> {
+        pub value: T,
+        pub meta: (u8, u8),
+        pub history: [T; 2],
+    }
+
+    // Listed fields must exist, including the first field of nested and indexed paths.
+    #[mutates(Record: ("value", "meta.0", "history[]"))]
+    impl<T: Copy> Record<T> {
+        pub fn record(&mut self, value: T) {
+            self.history[1] = self.history[0];
+            self.history[0] = self.value;
+            self.value = value;
+            self.meta.0 += 1;
+        }
+    }
+
+    // A method-level attribute on a generic impl compiles, since `Self` is not assumed to be the struct.
+    impl<T> Record<T> {
+        #[mutates(Record: ("value"))]
+        pub fn set(&mut self, value: T) {
+            self.value = value;
+        }
+    }
+
+    pub struct Builder {
+        pub first: u8,
+    }
+
+    // Neither is it when the impl block belongs to another type.
+    impl Builder {
+        #[mutates(Record: ("meta"))]
+        pub fn build(&mut self) -> Record<u8> {
+            let first = self.first;
+            let mut record = Record::default();
+            record.meta = (first, 0);
+            record
+        }
+    }
+
+    // Generic local instances are checked once their type is inferred.
+    #[mutates(Record: ("value"))]
+    fn fresh() -> u8 {
+        let mut record = Record { value: 0, meta: (0, 0), history: [0; 2] };
+        record.value = 4;
+        record.value
+    }
+
+    // ``` fails if uncommented, rustc reports the unknown field `valeu`
+    // #[mutates(Record: ("valeu"))]
+    // fn misspelled(record: &mut Record<u8>) {}
+    // ``` fails if uncommented, rustc reports the unknown field `valeu` on the local instance
+    // #[mutates(Record: ("valeu"))]
+    // fn built() -> u8 {
+    //     let record = Record { value: 0, meta: (0, 0), history: [0; 2] };
+    //     record.value
+    // }
+    // ``` fails if uncommented, not a field path
+    // #[mutates(Record: ("value()"))]
+    // fn invalid(record: &mut Record<u8>) {}
+
+    #[test]
+    fn test_field_names() {
+        let mut record = Record::default();
+        record.record(1);
+        record.record(2);
+        assert_eq!((record.value, record.meta, record.history), (2, (2, 0), [1, 0]));
+        record.set(3);
+        assert_eq!(record.value, 3);
+        assert_eq!(Builder { first: 5 }.build().meta, (5, 0));
+        assert_eq!(fresh(), 4);
+    }
+}
@@ -0,0 +1,84 @@
+#![no_std]
+#![deny(unsafe_code)]
+
+#[macro_use]
+extern crate proc_assertions;
+
+pub fn alloc_frames() {}
+pub fn allowed_function() {}
+
+pub struct MyStruct;
+
+impl MyStruct {
+    pub fn target_function(&self) {}
+    pub fn restricted_function(&self) {}
+
+    // None of the denylisted functions are called.
+    #[nocalls("alloc_frames", "restricted_function")]
+    pub fn allowed_caller(&self) {
+        allowed_function();
+        self.target_function();
+    }
+
+    // ``` fails
+    // #[nocalls("alloc_frames", "restricted_function")]
+    // pub fn unauthorized_caller(&self) {
+    //     alloc_frames();
+    //     self.restricted_function();
+    // }
+}
+
+#[cfg(test)]
+mod simple_tests {
+    use super::*;
+
+    #[test]
+    fn test_denied_function_not_called() {
+        #[allow(dead_code)]
+        #[nocalls("alloc_frames")]
+        pub fn my_function() {
+            allowed_function();
+        }
+
+        my_function();
+    }
+
+    #[test]
+    fn test_empty_denylist() {
+        #[allow(dead_code)]
+        #[nocalls()]
+        pub fn my_function() {
+            alloc_frames();
+        }
+
+        my_function();
+    }
+}
+
+mod nested_tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_nocalls() {
+        #[allow(dead_code)]
+        #[nocalls("alloc_frames")]
+        pub fn my_function() {
+            let name = || {
+                while false {
+                    for _ in 0..5 {
+                        // ``` fails if uncommented
+                        // alloc_frames();
+                        allowed_function();
+                    }
+                }
+
+                // ``` fails if uncommented
+                // let _i = { alloc_frames(); };
+            };
+
+            name();
+        }
+
+        my_function();
+    }
+}
@@ -1,5 +1,6 @@
 #![no_std]
 #![deny(unsafe_code)]
+#![allow(clippy::no_effect, clippy::let_unit_value)]
 
 #[macro_use]
 extern crate proc_assertions;
@@ -90,3 +91,277 @@ mod nested_tests {
         name();
     }
 }
+
+// Regression tests: `else if` chains used to overflow the macro stack.
+mod else_if_tests {
+    use super::*;
+
+    #[test]
+    fn test_else_if_nomutate() {
+        #[nomutates(MyStruct: ("field1"))]
+        fn my_function(instance: &mut MyStruct, input: i32) {
+            if input == 0 {
+                instance.field2 = 0;
+            } else if input == 1 {
+                instance.field2 += 1;
+            } else if input == 2 {
+                instance.field3 = 2;
+                // ``` fails if uncommented
+                // instance.field1 = 2;
+            }
+        }
+
+        let mut instance = MyStruct::default();
+        my_function(&mut instance, 1);
+        assert_eq!(instance.field2, 1);
+    }
+}
+
+mod classification_tests {
+    use super::*;
+
+    // Comparisons and arithmetic on a denylisted field are not mutations.
+    #[nomutates(MyStruct: ("field1"))]
+    fn compare(instance: &mut MyStruct, input: i32) -> bool {
+        instance.field2 = instance.field1 + input;
+        // ``` fails if uncommented, the field is borrowed mutably
+        // let _field = &mut instance.field1;
+        // ``` fails if uncommented, the whole instance is overwritten
+        // *instance = MyStruct::default();
+        instance.field1 == input || instance.field2 > input
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let mut instance = MyStruct::default();
+        assert!(compare(&mut instance, 0));
+        assert_eq!(instance.field2, 0);
+    }
+}
+
+mod replace_tests {
+    extern crate alloc;
+    use alloc::string::String;
+
+    pub struct Named {
+        pub name: String,
+        pub len: usize,
+        pub alias: Option<String>,
+    }
+
+    // `str::replace` only reads the field, unlike `mem::replace` or `Option::replace`.
+    #[nomutates(Named: ("name", "alias"))]
+    fn renamed(named: &mut Named) -> String {
+        named.len = named.name.len();
+        // ``` fails if uncommented
+        // let _old = core::mem::replace(&mut named.name, String::new());
+        // let _old = named.alias.replace(String::new());
+        named.name.replace("a", "b")
+    }
+
+    #[test]
+    fn test_str_replace() {
+        let mut named = Named { name: String::from("abc"), len: 0, alias: None };
+        assert_eq!(renamed(&mut named), "bbc");
+        assert_eq!((named.name.as_str(), named.len), ("abc", 3));
+    }
+}
+
+mod path_tests {
+    #[derive(Default)]
+    pub struct Counter {
+        pub count: u32,
+        pub limit: u32,
+    }
+
+    #[derive(Default)]
+    pub struct Table {
+        pub inner: Counter,
+        pub slots: [u32; 4],
+    }
+
+    impl Table {
+        // A nested write also touches its parent, and a parent write touches the nested paths.
+        #[nomutates(Table: ("inner.limit", "slots"))]
+        pub fn count(&mut self) {
+            self.inner.count += 1;
+            // ``` fails if uncommented
+            // self.inner.limit = 0;
+            // self.inner = Counter::default();
+            // self.slots[0] = 1;
+        }
+    }
+
+    #[test]
+    fn test_nested_paths() {
+        let mut table = Table::default();
+        table.count();
+        assert_eq!(table.inner.count, 1);
+        assert_eq!(table.inner.limit, 0);
+        assert_eq!(table.slots[0], 0);
+    }
+}
+
+mod pattern_tests {
+    use super::*;
+
+    // Bindings into a denylisted field are checked like the field itself.
+    #[nomutates(MyStruct: ("field1"))]
+    fn reset(instance: &mut MyStruct, input: Option<i32>) {
+        let MyStruct { field2, .. } = instance;
+        *field2 = 0;
+        if let Some(mut value) = input {
+            value += instance.field1;
+            instance.field3 = value;
+        }
+        // ``` fails if uncommented
+        // let MyStruct { field1, .. } = instance;
+        // *field1 = 0;
+    }
+
+    #[test]
+    fn test_patterns() {
+        let mut instance = MyStruct { field1: 1, field2: 2, field3: 3 };
+        reset(&mut instance, Some(1));
+        assert_eq!(instance.field1, 1);
+        assert_eq!(instance.field2, 0);
+        assert_eq!(instance.field3, 2);
+    }
+}
+
+mod control_flow_tests {
+    use super::*;
+
+    fn identity(value: i32) -> i32 {
+        value
+    }
+
+    // Denylisted fields are checked inside every kind of expression.
+    #[allow(clippy::never_loop)]
+    #[nomutates(MyStruct: ("field1"))]
+    fn walk(instance: &mut MyStruct, input: Option<i32>) -> i32 {
+        let Some(value) = input else {
+            return { instance.field2 += 1; 0 };
+        };
+        let total = loop {
+            break { instance.field2 += 1; value };
+        };
+        let _pair = (identity({ instance.field3 = total; 1 }), [{ instance.field2 += 1; 2 }]);
+        assert!({ instance.field3 += 1; true });
+        let _future = async { instance.field2 += 1 };
+        match input {
+            Some(0) => instance.field3 += 1,
+            Some(_) => instance.field2 += 1,
+            None => {}
+        }
+        #[allow(unsafe_code, unused_unsafe)]
+        unsafe { instance.field3 += 1 };
+        // ``` fails if uncommented
+        // let Some(_) = input else { instance.field1 = 1; return 0; };
+        // loop { break { instance.field1 = 1; 0 }; };
+        // let _pair = (identity({ instance.field1 = 1; 1 }), [{ instance.field1 = 1; 2 }]);
+        // assert!({ instance.field1 = 1; true });
+        // let _future = async { instance.field1 = 1 };
+        // match input { Some(0) => {} Some(_) => instance.field1 = 1, None => {} }
+        // #[allow(unsafe_code, unused_unsafe)]
+        // unsafe { instance.field1 = 1 };
+        instance.field1 + instance.field2 + instance.field3
+    }
+
+    #[test]
+    fn test_control_flow() {
+        let mut instance = MyStruct::default();
+        assert_eq!(walk(&mut instance, None), 0);
+        assert_eq!(walk(&mut instance, Some(3)), 9);
+        assert_eq!(instance.field1, 0);
+    }
+}
+
+mod policy_tests {
+    #[derive(Default)]
+    #[mutation_policies(identity = ("id", "generation"))]
+    pub struct Handle {
+        pub id: u32,
+        pub generation: u32,
+        pub uses: u32,
+    }
+
+    #[nomutates(Handle::identity)]
+    fn touch(handle: &mut Handle) -> u32 {
+        handle.uses += 1;
+        // ``` fails if uncommented
+        // handle.generation += 1;
+        handle.id
+    }
+
+    #[test]
+    fn test_policies() {
+        let mut handle = Handle { id: 3, ..Handle::default() };
+        assert_eq!(touch(&mut handle), 3);
+        assert_eq!((handle.uses, handle.generation), (1, 0));
+    }
+}
+
+mod field_name_tests {
+    use super::*;
+
+    // A misspelled field would otherwise protect nothing.
+    #[nomutates(MyStruct: ("field1", "field2"))]
+    fn bump(instance: &mut MyStruct) {
+        instance.field3 += 1;
+    }
+
+    // ``` fails if uncommented, rustc reports the unknown field `feild1`
+    // #[nomutates(MyStruct: ("feild1"))]
+    // fn misspelled(instance: &mut MyStruct) {}
+
+    mod inner {
+        pub struct Hidden {
+            pub a: u32,
+        }
+    }
+
+    pub struct Wrap {
+        pub hidden: inner::Hidden,
+    }
+
+    // `Hidden` is not in scope by name and `self` is not assumed to be one, so its fields are not referenced.
+    impl Wrap {
+        #[nomutates(Hidden: ("a"))]
+        fn touch(&mut self) {}
+    }
+
+    #[test]
+    fn test_field_names() {
+        let mut instance = MyStruct::default();
+        bump(&mut instance);
+        assert_eq!(instance.field3, 1);
+        let mut wrap = Wrap { hidden: inner::Hidden { a: 2 } };
+        wrap.touch();
+        assert_eq!(wrap.hidden.a, 2);
+    }
+}
+
+mod multiple_struct_tests {
+    use super::*;
+
+    #[derive(Default)]
+    pub struct Other {
+        pub value: i32,
+    }
+
+    #[nomutates(MyStruct: ("field1"), Other: ("value"))]
+    fn copy(instance: &mut MyStruct, other: &mut Other) {
+        instance.field2 = other.value;
+        // ``` fails if uncommented
+        // other.value = instance.field1;
+    }
+
+    #[test]
+    fn test_multiple_structs() {
+        let mut instance = MyStruct::default();
+        let mut other = Other { value: 3 };
+        copy(&mut instance, &mut other);
+        assert_eq!(instance.field2, 3);
+    }
+}